@@ -14,6 +14,7 @@ mkdir -p docs
 wasm-bindgen --no-typescript --target web --out-dir ./docs/ --out-name "match-3" ./target/wasm32-unknown-unknown/release/match-3-game.wasm
 
 cp ./src/index.html ./docs/
+cp -r ./assets ./docs/
 
 git add -A
 git commit -m "Build"
@@ -0,0 +1,81 @@
+# Generates the campaign map's icon atlas, one 64x64 tile per `MapIcon` in declaration order.
+# Reuses the drawing helpers from generate-shape-art.py.
+#
+# Usage: python3 scripts/generate-map-icons.py
+
+import importlib.util
+import os
+
+HERE = os.path.dirname(__file__)
+OUT = os.path.join(HERE, "..", "assets", "map", "icons.png")
+
+spec = importlib.util.spec_from_file_location("shape_art", os.path.join(HERE, "generate-shape-art.py"))
+art = importlib.util.module_from_spec(spec)
+spec.loader.exec_module(art)
+
+circle, segment, triangle, star = art.circle, art.segment, art.triangle, art.star
+rounded_square = art.rounded_square
+
+WHITE = (255, 255, 255)
+DARK = (30, 30, 35)
+LOCKED = (113, 113, 122)
+LOCKED_DARK = (63, 63, 70)
+AVAILABLE = (34, 197, 94)
+COMPLETED = (234, 179, 8)
+STAR = (250, 204, 21)
+STAR_EMPTY = (82, 82, 91)
+MARKER = (239, 68, 68)
+
+
+def locked(x, y):
+    if not circle(x, y, 32, 32, 28):
+        return None
+    shackle = segment(x, y, 24, 30, 24, 20, 5) or segment(x, y, 40, 30, 40, 20, 5)
+    arch = circle(x, y, 32, 20, 10.5) and not circle(x, y, 32, 20, 5.5) and y <= 20
+    if shackle or arch:
+        return WHITE
+    if 19 <= x <= 45 and 29 <= y <= 48:
+        return LOCKED_DARK if circle(x, y, 32, 37, 3) or segment(x, y, 32, 37, 32, 43, 3) else WHITE
+    return LOCKED
+
+
+def available(x, y):
+    if not circle(x, y, 32, 32, 28):
+        return None
+    return WHITE if triangle(x, y, (25, 18), (25, 46), (48, 32)) else AVAILABLE
+
+
+def completed(x, y):
+    if not circle(x, y, 32, 32, 28):
+        return None
+    tick = segment(x, y, 19, 33, 28, 42, 6) or segment(x, y, 28, 42, 46, 22, 6)
+    return WHITE if tick else COMPLETED
+
+
+def star_filled(x, y):
+    return STAR if star(x, y, 32, 34, 28, 12) else None
+
+
+def star_empty(x, y):
+    return STAR_EMPTY if star(x, y, 32, 34, 28, 12) else None
+
+
+def marker(x, y):
+    pin = circle(x, y, 32, 24, 18) or triangle(x, y, (17, 32), (47, 32), (32, 60))
+    if not pin:
+        return None
+    return WHITE if circle(x, y, 32, 24, 7) else MARKER
+
+
+# In `MapIcon` order: Locked, Available, Completed, Star, EmptyStar, Marker
+TILES = [locked, available, completed, star_filled, star_empty, marker]
+
+
+def main():
+    tiles = [art.render_tile(draw) for draw in TILES]
+    rows = [sum((tile[y] for tile in tiles), []) for y in range(art.TILE)]
+    art.write_png(OUT, rows)
+
+
+if __name__ == "__main__":
+    main()
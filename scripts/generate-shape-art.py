@@ -0,0 +1,266 @@
+# Generates the default shape atlas, one 64x64 tile per `Shape` in declaration order.
+# Colored shapes are drawn in white so they can be tinted from the palette, and each has its own
+# silhouette so they can be told apart without color.
+#
+# Usage: python3 scripts/generate-shape-art.py
+
+import math
+import os
+import struct
+import zlib
+
+TILE = 64
+SAMPLES = 4
+OUT = os.path.join(os.path.dirname(__file__), "..", "assets", "themes", "default", "shapes.png")
+
+WHITE = (255, 255, 255)
+SHADE = (200, 200, 200)
+DARK = (30, 30, 35)
+BOMB = (40, 40, 45)
+SPARK = (251, 146, 60)
+LINER = (34, 211, 238)
+ELIMINATOR = (168, 85, 247)
+CROSS = (241, 245, 249)
+ICE = (186, 230, 253)
+STONE = (120, 113, 108)
+STONE_DARK = (87, 83, 78)
+CHOCOLATE = (120, 53, 15)
+CHOCOLATE_DARK = (90, 40, 10)
+INGREDIENT = (253, 230, 138)
+LEAF = (34, 197, 94)
+
+
+def rounded_square(x, y, inset, radius):
+    lo, hi = inset, TILE - inset
+    cx = min(max(x, lo + radius), hi - radius)
+    cy = min(max(y, lo + radius), hi - radius)
+    return (x - cx) ** 2 + (y - cy) ** 2 <= radius**2 and lo <= x <= hi and lo <= y <= hi
+
+
+def circle(x, y, cx, cy, r):
+    return (x - cx) ** 2 + (y - cy) ** 2 <= r**2
+
+
+def segment(x, y, x1, y1, x2, y2, width):
+    dx, dy = x2 - x1, y2 - y1
+    t = max(0, min(1, ((x - x1) * dx + (y - y1) * dy) / (dx * dx + dy * dy)))
+    return math.hypot(x - (x1 + t * dx), y - (y1 + t * dy)) <= width / 2
+
+
+def triangle(x, y, a, b, c):
+    def side(p, q):
+        return (x - q[0]) * (p[1] - q[1]) - (p[0] - q[0]) * (y - q[1])
+
+    d1, d2, d3 = side(a, b), side(b, c), side(c, a)
+    neg = d1 < 0 or d2 < 0 or d3 < 0
+    pos = d1 > 0 or d2 > 0 or d3 > 0
+    return not (neg and pos)
+
+
+def star(x, y, cx, cy, outer, inner):
+    angle = math.atan2(y - cy, x - cx) + math.pi / 2
+    r = math.hypot(x - cx, y - cy)
+    sector = (angle % (2 * math.pi / 5)) / (2 * math.pi / 5)
+    edge = inner + (outer - inner) * abs(1 - 2 * sector)
+    return r <= edge
+
+
+def double_arrow(x, y, horizontal):
+    if not horizontal:
+        x, y = y, x
+    shaft = segment(x, y, 18, 32, 46, 32, 6)
+    left = triangle(x, y, (10, 32), (22, 22), (22, 42))
+    right = triangle(x, y, (54, 32), (42, 22), (42, 42))
+    return shaft or left or right
+
+
+def polygon(x, y, sides, r, rotation=0.0):
+    angle = math.atan2(y - 32, x - 32) - rotation
+    sector = 2 * math.pi / sides
+    offset = (angle % sector) - sector / 2
+    return math.hypot(x - 32, y - 32) * math.cos(offset) <= r * math.cos(sector / 2)
+
+
+def heart(x, y):
+    return (
+        circle(x, y, 22, 24, 12)
+        or circle(x, y, 42, 24, 12)
+        or triangle(x, y, (11, 29), (53, 29), (32, 56))
+    )
+
+
+def crescent(x, y):
+    return circle(x, y, 32, 32, 26) and not circle(x, y, 44, 24, 20)
+
+
+# In `Shape` order: Red, Blue, Green, Pink, Orange, Purple, Yellow
+GLYPHS = [
+    lambda x, y: circle(x, y, 32, 32, 26),
+    lambda x, y: polygon(x, y, 4, 30),
+    lambda x, y: triangle(x, y, (32, 5), (60, 56), (4, 56)),
+    heart,
+    lambda x, y: rounded_square(x, y, 7, 6),
+    lambda x, y: polygon(x, y, 6, 29),
+    crescent,
+]
+
+
+def gem(glyph):
+    def draw(x, y):
+        if not glyph(x, y):
+            return None
+        if circle(x, y, 24, 22, 5):
+            return WHITE
+        shade = 1 - 0.3 * ((x + y) / (2 * TILE))
+        return tuple(int(c * shade) for c in WHITE)
+
+    return draw
+
+
+def bomb(x, y):
+    if segment(x, y, 40, 20, 48, 10, 4):
+        return SPARK if circle(x, y, 48, 10, 5) else DARK
+    if circle(x, y, 48, 10, 5):
+        return SPARK
+    if circle(x, y, 30, 36, 20):
+        return (90, 90, 95) if circle(x, y, 23, 29, 5) else BOMB
+    return None
+
+
+def liner(horizontal):
+    def draw(x, y):
+        if not rounded_square(x, y, 4, 12):
+            return None
+        return DARK if double_arrow(x, y, horizontal) else LINER
+
+    return draw
+
+
+def eliminator(x, y):
+    if not rounded_square(x, y, 4, 12):
+        return None
+    return WHITE if star(x, y, 32, 33, 22, 9) else ELIMINATOR
+
+
+def cross(x, y):
+    if not rounded_square(x, y, 4, 12):
+        return None
+    if double_arrow(x, y, True) or double_arrow(x, y, False):
+        return DARK
+    return CROSS
+
+
+def ice(x, y):
+    if not rounded_square(x, y, 2, 6):
+        return None
+    cracks = [
+        ((10, 14), (28, 30)),
+        ((28, 30), (24, 50)),
+        ((28, 30), (52, 22)),
+        ((40, 44), (54, 54)),
+    ]
+    if any(segment(x, y, *a, *b, 2) for a, b in cracks):
+        return WHITE
+    return ICE
+
+
+def stone(x, y):
+    if not rounded_square(x, y, 2, 8):
+        return None
+    speckles = [(16, 18, 4), (40, 14, 3), (46, 40, 5), (20, 44, 3), (30, 30, 2)]
+    if any(circle(x, y, cx, cy, r) for cx, cy, r in speckles):
+        return STONE_DARK
+    return STONE
+
+
+def chocolate(x, y):
+    if not rounded_square(x, y, 2, 4):
+        return None
+    in_gap = any(abs(x - g) < 1.5 or abs(y - g) < 1.5 for g in (22, 42))
+    return CHOCOLATE_DARK if in_gap else CHOCOLATE
+
+
+def ingredient(x, y):
+    if segment(x, y, 32, 10, 32, 20, 3):
+        return CHOCOLATE
+    if triangle(x, y, (32, 14), (48, 6), (44, 18)):
+        return LEAF
+    if circle(x, y, 32, 38, 20):
+        return (255, 250, 220) if circle(x, y, 25, 31, 5) else INGREDIENT
+    return None
+
+
+TILES = [gem(glyph) for glyph in GLYPHS] + [
+    bomb,
+    liner(True),
+    liner(False),
+    eliminator,
+    cross,
+    ice,
+    stone,
+    chocolate,
+    ingredient,
+]
+
+
+def render_tile(draw):
+    pixels = []
+    for py in range(TILE):
+        row = []
+        for px in range(TILE):
+            total = [0, 0, 0, 0]
+            for sy in range(SAMPLES):
+                for sx in range(SAMPLES):
+                    color = draw(px + (sx + 0.5) / SAMPLES, py + (sy + 0.5) / SAMPLES)
+                    if color is not None:
+                        total[0] += color[0]
+                        total[1] += color[1]
+                        total[2] += color[2]
+                        total[3] += 1
+            coverage = total[3]
+            if coverage == 0:
+                row.append((0, 0, 0, 0))
+            else:
+                row.append(
+                    (
+                        total[0] // coverage,
+                        total[1] // coverage,
+                        total[2] // coverage,
+                        255 * coverage // (SAMPLES * SAMPLES),
+                    )
+                )
+        pixels.append(row)
+    return pixels
+
+
+def write_png(path, rows):
+    height = len(rows)
+    width = len(rows[0])
+    raw = b"".join(b"\x00" + bytes(c for pixel in row for c in pixel) for row in rows)
+
+    def chunk(kind, data):
+        return (
+            struct.pack(">I", len(data))
+            + kind
+            + data
+            + struct.pack(">I", zlib.crc32(kind + data) & 0xFFFFFFFF)
+        )
+
+    png = b"\x89PNG\r\n\x1a\n"
+    png += chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0))
+    png += chunk(b"IDAT", zlib.compress(raw, 9))
+    png += chunk(b"IEND", b"")
+
+    os.makedirs(os.path.dirname(path), exist_ok=True)
+    with open(path, "wb") as f:
+        f.write(png)
+
+
+def main():
+    tiles = [render_tile(draw) for draw in TILES]
+    rows = [sum((tile[y] for tile in tiles), []) for y in range(TILE)]
+    write_png(OUT, rows)
+
+
+if __name__ == "__main__":
+    main()
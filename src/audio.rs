@@ -0,0 +1,432 @@
+use std::{f32::consts::TAU, sync::Arc, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, Source, Volume},
+    prelude::*,
+};
+
+use crate::{
+    board::{board_running, shape::Shape, Deletion, MatchMade, MoveMade, SwapShapes},
+    settings::Settings,
+    GameState,
+};
+
+pub fn audio(app: &mut App) {
+    app.add_audio_source::<Synth>()
+        .init_resource::<Sounds>()
+        .init_resource::<Cascade>()
+        .add_systems(
+            FixedPostUpdate,
+            (play_swap_sounds, play_match_sounds, play_special_sounds).run_if(board_running),
+        )
+        .add_systems(OnEnter(GameState::LevelComplete), play_win_sound)
+        .add_systems(OnEnter(GameState::WinScreen), play_win_sound)
+        .add_systems(OnEnter(GameState::LevelFailed), play_lose_sound)
+        .add_systems(
+            Update,
+            (
+                play_click_sounds,
+                change_music.run_if(state_changed::<GameState>),
+                apply_music_volume.run_if(resource_changed::<Settings>),
+            ),
+        );
+}
+
+const SAMPLE_RATE: u32 = 44_100;
+
+// A sound rendered up front from a list of tones, so the game doesn't need any audio files
+#[derive(Asset, TypePath, Clone)]
+pub struct Synth {
+    samples: Arc<[f32]>,
+}
+
+impl Synth {
+    fn new(tones: &[Tone]) -> Self {
+        let samples = tones.iter().flat_map(Tone::render).collect::<Vec<_>>();
+        Synth {
+            samples: samples.into(),
+        }
+    }
+}
+
+pub struct SynthDecoder {
+    samples: Arc<[f32]>,
+    index: usize,
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.index).copied();
+        self.index += 1;
+        sample
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            self.samples.len() as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthDecoder {
+            samples: self.samples.clone(),
+            index: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Wave {
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+    Noise,
+    Rest,
+}
+
+#[derive(Clone, Copy)]
+struct Tone {
+    wave: Wave,
+    // The frequency slides from `from` to `to` over the tone
+    from: f32,
+    to: f32,
+    seconds: f32,
+    volume: f32,
+    // Fades out over the tone instead of holding steady
+    decay: bool,
+}
+
+impl Tone {
+    fn new(wave: Wave, from: f32, to: f32, seconds: f32) -> Self {
+        Tone {
+            wave,
+            from,
+            to,
+            seconds,
+            volume: 0.5,
+            decay: true,
+        }
+    }
+
+    fn note(wave: Wave, midi: u8, seconds: f32) -> Self {
+        let frequency = 440.0 * 2f32.powf((midi as f32 - 69.0) / 12.0);
+        Tone::new(wave, frequency, frequency, seconds)
+    }
+
+    fn rest(seconds: f32) -> Self {
+        Tone::new(Wave::Rest, 0.0, 0.0, seconds)
+    }
+
+    fn volume(self, volume: f32) -> Self {
+        Tone { volume, ..self }
+    }
+
+    fn held(self) -> Self {
+        Tone {
+            decay: false,
+            ..self
+        }
+    }
+
+    fn render(&self) -> Vec<f32> {
+        let length = (self.seconds * SAMPLE_RATE as f32) as usize;
+        let mut phase = 0.0;
+        let mut noise = 0x1234_5678u32;
+
+        (0..length)
+            .map(|i| {
+                let progress = i as f32 / length as f32;
+                let frequency = self.from + (self.to - self.from) * progress;
+                phase = (phase + frequency / SAMPLE_RATE as f32).fract();
+
+                let sample = match self.wave {
+                    Wave::Sine => (phase * TAU).sin(),
+                    Wave::Square => {
+                        if phase < 0.5 {
+                            0.5
+                        } else {
+                            -0.5
+                        }
+                    }
+                    Wave::Saw => phase - 0.5,
+                    Wave::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                    Wave::Noise => {
+                        noise ^= noise << 13;
+                        noise ^= noise >> 17;
+                        noise ^= noise << 5;
+                        noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+                    }
+                    Wave::Rest => 0.0,
+                };
+
+                // A short attack and release keep tones from clicking
+                let edge = 0.005 * SAMPLE_RATE as f32;
+                let attack = (i as f32 / edge).min(1.0);
+                let release = ((length - i) as f32 / edge).min(1.0);
+                let envelope = if self.decay {
+                    (1.0 - progress).powi(2)
+                } else {
+                    1.0
+                };
+
+                sample * self.volume * envelope * attack * release
+            })
+            .collect()
+    }
+}
+
+#[derive(Resource)]
+struct Sounds {
+    swap: Handle<Synth>,
+    invalid_swap: Handle<Synth>,
+    matched: Handle<Synth>,
+    bomb: Handle<Synth>,
+    liner: Handle<Synth>,
+    eliminator: Handle<Synth>,
+    cross: Handle<Synth>,
+    win: Handle<Synth>,
+    lose: Handle<Synth>,
+    click: Handle<Synth>,
+    start_screen_music: Handle<Synth>,
+    map_music: Handle<Synth>,
+    board_music: Handle<Synth>,
+    explanation_music: Handle<Synth>,
+    win_screen_music: Handle<Synth>,
+}
+
+impl FromWorld for Sounds {
+    fn from_world(world: &mut World) -> Self {
+        use Wave::*;
+
+        let mut synths = world.resource_mut::<Assets<Synth>>();
+        let mut add = |tones: &[Tone]| synths.add(Synth::new(tones));
+
+        // Melodies as midi notes, with 0 as a rest
+        let melody = |wave: Wave, notes: &[u8], seconds: f32| {
+            notes
+                .iter()
+                .map(|&midi| match midi {
+                    0 => Tone::rest(seconds),
+                    midi => Tone::note(wave, midi, seconds).volume(0.15),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        Sounds {
+            swap: add(&[Tone::new(Sine, 500.0, 800.0, 0.08)]),
+            invalid_swap: add(&[Tone::new(Square, 180.0, 110.0, 0.18).volume(0.3)]),
+            matched: add(&[Tone::new(Sine, 660.0, 660.0, 0.25)]),
+            bomb: add(&[
+                Tone::new(Noise, 0.0, 0.0, 0.05).volume(0.6),
+                Tone::new(Sine, 90.0, 40.0, 0.4).volume(0.8),
+            ]),
+            liner: add(&[Tone::new(Saw, 300.0, 1200.0, 0.25).volume(0.3)]),
+            eliminator: add(&[
+                Tone::note(Triangle, 81, 0.06),
+                Tone::note(Triangle, 85, 0.06),
+                Tone::note(Triangle, 88, 0.06),
+                Tone::note(Triangle, 93, 0.2),
+            ]),
+            cross: add(&[
+                Tone::new(Saw, 1200.0, 300.0, 0.15).volume(0.3),
+                Tone::new(Saw, 300.0, 1200.0, 0.15).volume(0.3),
+            ]),
+            win: add(&[
+                Tone::note(Triangle, 72, 0.12),
+                Tone::note(Triangle, 76, 0.12),
+                Tone::note(Triangle, 79, 0.12),
+                Tone::note(Triangle, 84, 0.5),
+            ]),
+            lose: add(&[
+                Tone::note(Triangle, 67, 0.15),
+                Tone::note(Triangle, 63, 0.15),
+                Tone::note(Triangle, 60, 0.15),
+                Tone::note(Triangle, 55, 0.5),
+            ]),
+            click: add(&[Tone::new(Sine, 1000.0, 900.0, 0.03).volume(0.3).held()]),
+            start_screen_music: add(&melody(Triangle, &[60, 64, 67, 72, 67, 64, 60, 0], 0.4)),
+            map_music: add(&melody(Triangle, &[62, 65, 69, 65, 67, 71, 74, 0], 0.35)),
+            board_music: add(&melody(
+                Sine,
+                &[
+                    57, 60, 64, 60, 55, 59, 62, 59, 53, 57, 60, 57, 55, 59, 62, 0,
+                ],
+                0.25,
+            )),
+            explanation_music: add(&melody(Triangle, &[67, 71, 74, 79, 74, 71, 0, 0], 0.3)),
+            win_screen_music: add(&melody(Triangle, &[72, 76, 79, 84, 79, 76, 72, 0], 0.3)),
+        }
+    }
+}
+
+// How many clears in a row one move has caused, which raises the pitch of the match sound
+#[derive(Resource, Default)]
+struct Cascade(u32);
+
+#[derive(Component)]
+struct Music(Handle<Synth>);
+
+fn play(commands: &mut Commands, sound: &Handle<Synth>, volume: f32, speed: f32) {
+    commands.spawn((
+        AudioPlayer(sound.clone()),
+        PlaybackSettings::DESPAWN
+            .with_volume(Volume::new(volume))
+            .with_speed(speed),
+        Name::new("Sound Effect"),
+    ));
+}
+
+// Swaps that weren't allowed never make a move
+fn play_swap_sounds(
+    mut swap_shapes: EventReader<SwapShapes>,
+    mut move_made: EventReader<MoveMade>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let swaps = swap_shapes.read().count();
+    let moves = move_made.read().count();
+
+    if moves > 0 {
+        play(&mut commands, &sounds.swap, settings.effects_volume, 1.0);
+    } else if swaps > 0 {
+        play(
+            &mut commands,
+            &sounds.invalid_swap,
+            settings.effects_volume,
+            1.0,
+        );
+    }
+}
+
+fn play_match_sounds(
+    mut match_made: EventReader<MatchMade>,
+    mut move_made: EventReader<MoveMade>,
+    mut cascade: ResMut<Cascade>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    if move_made.read().count() > 0 {
+        cascade.0 = 0;
+    }
+
+    if match_made.read().count() > 0 {
+        let speed = 1.0 + 0.12 * cascade.0.min(8) as f32;
+        play(
+            &mut commands,
+            &sounds.matched,
+            settings.effects_volume,
+            speed,
+        );
+        cascade.0 += 1;
+    }
+}
+
+// Each kind of special is only heard once however many go off together
+fn play_special_sounds(
+    deleted: Query<&Shape, Added<Deletion>>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let mut to_play = vec![];
+    for special in deleted.iter().filter(|s| s.is_special()) {
+        let sound = match special {
+            Shape::Bomb => &sounds.bomb,
+            Shape::HorizontalLiner | Shape::VerticalLiner => &sounds.liner,
+            Shape::Eliminator => &sounds.eliminator,
+            _ => &sounds.cross,
+        };
+        if !to_play.contains(&sound) {
+            to_play.push(sound);
+        }
+    }
+
+    for sound in to_play {
+        play(&mut commands, sound, settings.effects_volume, 1.0);
+    }
+}
+
+fn play_win_sound(sounds: Res<Sounds>, settings: Res<Settings>, mut commands: Commands) {
+    play(&mut commands, &sounds.win, settings.effects_volume, 1.0);
+}
+
+fn play_lose_sound(sounds: Res<Sounds>, settings: Res<Settings>, mut commands: Commands) {
+    play(&mut commands, &sounds.lose, settings.effects_volume, 1.0);
+}
+
+// Shapes are buttons too, but they have their own sounds
+fn play_click_sounds(
+    interactions: Query<&Interaction, (Changed<Interaction>, Without<Shape>)>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    if interactions.iter().any(|i| *i == Interaction::Pressed) {
+        play(&mut commands, &sounds.click, settings.effects_volume, 1.0);
+    }
+}
+
+// The settings keep whatever was playing on the screen they were opened from
+fn change_music(
+    state: Res<State<GameState>>,
+    music: Query<(Entity, &Music)>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let track = match state.get() {
+        GameState::StartScreen => &sounds.start_screen_music,
+        GameState::Map | GameState::Briefing => &sounds.map_music,
+        GameState::Board => &sounds.board_music,
+        GameState::ExplanationScreen | GameState::LevelComplete | GameState::LevelFailed => {
+            &sounds.explanation_music
+        }
+        GameState::WinScreen => &sounds.win_screen_music,
+        GameState::Settings => return,
+    };
+
+    if music.iter().any(|(_, playing)| playing.0 == *track) {
+        return;
+    }
+
+    for (entity, _) in music.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    commands.spawn((
+        AudioPlayer(track.clone()),
+        PlaybackSettings::LOOP.with_volume(Volume::new(settings.music_volume)),
+        Music(track.clone()),
+        Name::new("Music"),
+    ));
+}
+
+fn apply_music_volume(settings: Res<Settings>, music: Query<&AudioSink, With<Music>>) {
+    for sink in music.iter() {
+        sink.set_volume(settings.music_volume);
+    }
+}
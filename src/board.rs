@@ -1,14 +1,22 @@
-use bevy::{color::palettes::tailwind::*, prelude::*};
-use rand::prelude::*;
+use bevy::{color::palettes::tailwind::*, ecs::schedule::ScheduleLabel, prelude::*};
+use rand::{prelude::*, rngs::StdRng};
 use std::iter::zip;
 
 #[derive(Component)]
 pub struct Board;
 
-use match_counter::MatchCounter;
+use match_counter::{LevelStatsText, MatchCounter};
+use obstacle::{Chain, HitPoints};
 use shape::*;
 
-use crate::{CurrentMap, GameState, MapFinishes, TotalMatches};
+use crate::{
+    focus::{cancel_just_pressed, direction_just_pressed, Focused},
+    map::{CampaignMap, CampaignMapHandle},
+    objective_complete,
+    settings::{closing_settings, opening_settings, spawn_settings_button, Settings},
+    BestScores, CampaignStats, CollectedIngredients, ColorCount, CurrentMap, FirstClear, GameState,
+    LevelObstacles, LevelState, LevelStats, MapFinishes, TotalMatches,
+};
 
 use utils::*;
 
@@ -16,90 +24,453 @@ use utils::*;
 pub struct MatchMade();
 
 #[derive(Event)]
-pub struct SwapShapes(Entity, Entity);
+pub struct SwapShapes(pub Entity, pub Entity);
+
+#[derive(Event, Default)]
+pub struct MoveMade();
+
+// How many shapes were cleared and refilled, obstacles that only took damage aren't counted
+#[derive(Event)]
+struct ShapesReplaced(u32);
 
 #[derive(Component)]
 pub struct Deletion;
 
+#[derive(Component)]
+pub struct Swapping;
+
+// Speeds are in percent of a shape's height per second
+#[derive(Component, Default)]
+pub struct Falling {
+    velocity: f32,
+    delay: f32,
+    bounced: bool,
+}
+
+#[derive(Resource)]
+pub struct Gravity {
+    pub acceleration: f32,
+    pub max_speed: f32,
+    // Fraction of the landing speed a shape bounces back up with
+    pub bounce: f32,
+    // How much later each column starts falling than the one to its left
+    pub column_delay: f32,
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity {
+            acceleration: 2000.0,
+            max_speed: 800.0,
+            bounce: 0.15,
+            column_delay: 0.03,
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct JustSwappedShapes([Entity; 2]);
 
+// The first shape clicked when swapping by clicking two shapes
+#[derive(Component)]
+pub struct Selected;
+
+// Seeds the starting layout, so that restarting can replay the same board
+#[derive(Resource, Default)]
+pub struct BoardSeed {
+    seed: u64,
+    replay: bool,
+}
+
+impl BoardSeed {
+    // Picks the next board's layout ahead of time, e.g for the briefing to show it
+    pub fn pick_next(&mut self) -> u64 {
+        self.seed = rand::thread_rng().gen();
+        self.replay = true;
+        self.seed
+    }
+}
+
+// Which cells a level's obstacles start in, worked out from the board's seed
+pub struct ObstacleLayout {
+    ingredients: Vec<usize>,
+    ice: Vec<usize>,
+    stone: Vec<usize>,
+    chains: Vec<usize>,
+    chocolate: Vec<usize>,
+    // What's left, in the order a starting booster picks from
+    free: Vec<usize>,
+}
+
+impl ObstacleLayout {
+    pub fn from_seed(seed: u64, level_obstacles: &LevelObstacles) -> Self {
+        ObstacleLayout::new(&mut StdRng::seed_from_u64(seed), level_obstacles)
+    }
+
+    fn new(rng: &mut StdRng, level_obstacles: &LevelObstacles) -> Self {
+        // Ingredients start in the top row so they have the whole board to fall through
+        if level_obstacles.ingredients > BOARD_SIZE {
+            warn!(
+                "No room in the top row for {} ingredients, placing {BOARD_SIZE}",
+                level_obstacles.ingredients
+            );
+        }
+        let ingredients = (0..BOARD_SIZE).choose_multiple(rng, level_obstacles.ingredients);
+
+        let mut indices = (0..BOARD_TOTAL_SHAPES)
+            .filter(|i| !ingredients.contains(i))
+            .collect::<Vec<_>>();
+        indices.shuffle(rng);
+
+        let mut indices = &indices[..];
+        ObstacleLayout {
+            ingredients,
+            ice: take_indices(&mut indices, level_obstacles.ice, "ice").to_vec(),
+            stone: take_indices(&mut indices, level_obstacles.stone, "stone").to_vec(),
+            chains: take_indices(&mut indices, level_obstacles.chains, "chains").to_vec(),
+            chocolate: take_indices(&mut indices, level_obstacles.chocolate, "chocolate").to_vec(),
+            free: indices.to_vec(),
+        }
+    }
+
+    // The obstacle that takes the place of a shape at this index, chains go on a shape instead
+    pub fn obstacle(&self, index: usize) -> Option<Shape> {
+        [
+            (&self.ingredients, Shape::Ingredient),
+            (&self.ice, Shape::Ice),
+            (&self.stone, Shape::Stone),
+            (&self.chocolate, Shape::Chocolate),
+        ]
+        .into_iter()
+        .find_map(|(indices, shape)| indices.contains(&index).then_some(shape))
+    }
+
+    pub fn chained(&self, index: usize) -> bool {
+        self.chains.contains(&index)
+    }
+}
+
+// Refills, eliminators and spreading chocolate draw from this so the same seed plays a board out
+// the same way, e.g a tutorial's moves cascade just like they were written
+#[derive(Resource)]
+pub struct BoardRng(StdRng);
+
+impl BoardRng {
+    pub fn seeded(seed: u64) -> Self {
+        BoardRng(StdRng::seed_from_u64(seed))
+    }
+}
+
+// Guides and demos always start from this, while levels are seeded with their board
+pub const TUTORIAL_SEED: u64 = 0;
+
+// Row and column of the shape focused with the keyboard or a gamepad
+#[derive(Resource, Default)]
+pub struct BoardCursor(Option<(i32, i32)>);
+
+// A special picked before the level to be on the board from the start
+#[derive(Resource, Default)]
+pub struct StartingBooster(pub Option<Shape>);
+
+// Which swaps the player can make, e.g tutorials only let through the move they're teaching.
+// Positions are 1-based (row, col) like in `utils`.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub enum AllowedSwaps {
+    #[default]
+    Any,
+    None,
+    // The two shapes at these positions, in either order
+    Between((i32, i32), (i32, i32)),
+    // Any swap involving a special, setting it off
+    WithSpecial,
+}
+
+impl AllowedSwaps {
+    pub fn allows(
+        &self,
+        shape1: &Entity,
+        shape2: &Entity,
+        board: &Children,
+        shapes: &Query<&Shape>,
+    ) -> bool {
+        let position = |shape| {
+            let (row, col) = get_row_col(shape, board);
+            (row as i32, col as i32)
+        };
+
+        match *self {
+            AllowedSwaps::Any => true,
+            AllowedSwaps::None => false,
+            AllowedSwaps::Between(a, b) => {
+                let swapped = [position(shape1), position(shape2)];
+                swapped == [a, b] || swapped == [b, a]
+            }
+            AllowedSwaps::WithSpecial => [shape1, shape2]
+                .iter()
+                .any(|e| shapes.get(**e).is_ok_and(|s| s.is_special())),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct BoardNodeRoot;
 
+// Each animation state goes back to `InPlay` once it's done, where matches are resolved and
+// input is taken
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
-enum BoardState {
+pub enum BoardState {
     #[default]
     InPlay,
+    AnimatingSwap,
+    AnimatingClear,
     AnimatingFallingShapes,
 }
 
 const BOARD_POSITION: Transform = Transform::from_xyz(-200.0, 200.0, 0.0);
 pub const BOARD_SIZE: usize = 5;
 const BOARD_TOTAL_SHAPES: usize = BOARD_SIZE * BOARD_SIZE;
+const POINTS_PER_SHAPE: u32 = 10;
 
 pub(crate) fn board(app: &mut App) {
     app.add_event::<SwapShapes>()
         .add_event::<MatchMade>()
+        .add_event::<MoveMade>()
+        .add_event::<ShapesReplaced>()
+        .init_resource::<hazard::HazardTurn>()
+        .init_resource::<ingredient::IngredientsToSpawn>()
+        .init_resource::<drag::Drag>()
+        .init_resource::<BoardCursor>()
+        .init_resource::<AllowedSwaps>()
+        .init_resource::<StartingBooster>()
+        .init_resource::<clear_effects::ClearTimer>()
+        .init_resource::<Gravity>()
+        .init_resource::<BoardSeed>()
+        .insert_resource(BoardRng::seeded(TUTORIAL_SEED))
+        .init_resource::<ShapeTheme>()
+        .init_resource::<ShapeArt>()
+        .add_systems(
+            Update,
+            apply_shape_art.run_if(resource_changed::<ShapeTheme>.or(resource_changed::<Settings>)),
+        )
         .init_state::<BoardState>()
         .insert_resource(JustSwappedShapes([
             Entity::from_raw(0),
             Entity::from_raw(0),
         ]))
         .add_systems(
-            OnEnter(GameState::Board),
+            SetUpBoard,
             (
+                pick_board_seed,
+                guide::start,
                 spawn_board,
                 match_counter::spawn,
                 spawn_shapes_into_board,
                 layout_nodes,
+                hazard::reset,
+                hint::reset,
+                // Reset on entering rather than leaving so the level complete screen can show them
+                reset_level_stats,
             )
                 .chain(),
         )
+        .add_systems(
+            OnEnter(GameState::Board),
+            set_up_board.run_if(not(closing_settings)),
+        )
         .add_systems(
             FixedUpdate,
             (
                 (
                     (
-                        write_swap_shape_event,
-                        handle_swap_shape_events,
-                        spawn_eliminators_from_matches.run_if(eliminator_unlocked),
-                        spawn_bombs_from_matches.run_if(bomb_unlocked),
-                        spawn_liners_from_matches.run_if(liner_unlocked),
+                        spawn_eliminators_from_matches.run_if(special_unlocked(Shape::Eliminator)),
+                        spawn_crosses_from_matches.run_if(special_unlocked(Shape::Cross)),
+                        spawn_bombs_from_matches.run_if(special_unlocked(Shape::Bomb)),
+                        spawn_liners_from_matches.run_if(special_unlocked(Shape::HorizontalLiner)),
                         handle_regular_matches,
-                        update_board_after_deletions,
+                        obstacle::damage,
+                        (hazard::track_destroyed, ingredient::collect)
+                            .run_if(in_state(GameState::Board)),
+                        clear_effects::start,
+                        hazard::grow.run_if(in_state(GameState::Board)),
+                        (
+                            write_swap_shape_event.run_if(click_allowed),
+                            handle_swap_shape_events,
+                        )
+                            .chain()
+                            .run_if(board_settled),
                     )
                         .chain()
                         .run_if(in_state(BoardState::InPlay)),
+                    animate_swaps.run_if(in_state(BoardState::AnimatingSwap)),
+                    (
+                        clear_effects::animate,
+                        (clear_effects::finish, update_board_after_deletions)
+                            .chain()
+                            .run_if(clear_effects::finished),
+                    )
+                        .chain()
+                        .run_if(in_state(BoardState::AnimatingClear)),
                     animate_shape_bottoms_to_0.run_if(in_state(BoardState::AnimatingFallingShapes)),
                 )
                     .chain(),
-                update_shape_color,
+                update_shape_image,
+                update_selected_outline,
+                obstacle::update_visuals,
+            )
+                .run_if(board_running),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                hazard::track_moves,
                 match_counter::update,
+                (update_level_stats, match_counter::update_level_stats_text).chain(),
+                hint::show.run_if(in_state(BoardState::InPlay)),
+                (hint::clear, hint::pulse).chain(),
+                (guide::advance, guide::point_out.run_if(board_idle))
+                    .chain()
+                    .after(hint::clear)
+                    .run_if(resource_exists::<guide::Guided>),
+            )
+                .run_if(in_state(LevelState::Playing)),
+        )
+        // Pointer releases only last a frame so dragging can't be handled in FixedUpdate
+        .add_systems(
+            Update,
+            (
+                cancel_selection,
+                (drag::start.run_if(board_settled), drag::follow, drag::end).run_if(drag_allowed),
             )
-                .run_if(in_state(GameState::Board)),
+                .chain()
+                .run_if(board_running)
+                .run_if(in_state(BoardState::InPlay)),
+        )
+        .add_systems(
+            Update,
+            (move_board_cursor, focus_board_cursor)
+                .chain()
+                .run_if(in_state(LevelState::Playing)),
+        )
+        .add_systems(
+            TearDownBoard,
+            (
+                delete_entities,
+                reset_total_matches,
+                reset_collected_ingredients,
+                reset_board_cursor,
+                reset_board_state,
+                guide::stop,
+            ),
         )
         .add_systems(
             OnExit(GameState::Board),
-            (delete_entities, reset_total_matches, update_map_finishes),
-        );
+            (
+                // Leaving without finishing, e.g quitting from the pause menu, doesn't count
+                (
+                    update_map_finishes,
+                    record_campaign_stats,
+                    record_best_score,
+                )
+                    .chain()
+                    .run_if(objective_complete),
+                tear_down_board,
+            )
+                .chain()
+                .run_if(not(opening_settings)),
+        )
+        .init_resource::<hint::IdleTime>();
+}
+
+// Building and clearing the board are their own schedules so a restart can run them without
+// pretending to leave and re-enter the board state
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SetUpBoard;
+
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct TearDownBoard;
+
+fn set_up_board(world: &mut World) {
+    world.run_schedule(SetUpBoard);
+}
+
+fn tear_down_board(world: &mut World) {
+    world.run_schedule(TearDownBoard);
+}
+
+// Rebuilds the board the same way entering it does, without counting the level as finished
+pub fn restart_level(same_layout: bool) -> impl FnOnce(&mut World) {
+    move |world: &mut World| {
+        world.resource_mut::<BoardSeed>().replay = same_layout;
+        tear_down_board(world);
+        set_up_board(world);
+    }
+}
+
+// Levels are played on the board and tutorials can show one live, with the same rules
+pub fn board_running(
+    game_state: Res<State<GameState>>,
+    level_state: Option<Res<State<LevelState>>>,
+    board: Query<(), With<Board>>,
+) -> bool {
+    let playing = match game_state.get() {
+        GameState::Board => level_state.is_some_and(|state| *state.get() == LevelState::Playing),
+        GameState::ExplanationScreen => true,
+        _ => false,
+    };
+
+    playing && !board.is_empty()
+}
+
+// Nothing is moving and nothing is about to, so the next move can be made
+pub fn board_idle(
+    board_state: Res<State<BoardState>>,
+    next_board_state: Res<NextState<BoardState>>,
+) -> bool {
+    *board_state.get() == BoardState::InPlay && matches!(*next_board_state, NextState::Unchanged)
+}
+
+fn pick_board_seed(mut board_seed: ResMut<BoardSeed>, mut board_rng: ResMut<BoardRng>) {
+    if board_seed.replay {
+        board_seed.replay = false;
+    } else {
+        board_seed.seed = rand::thread_rng().gen();
+    }
+    *board_rng = BoardRng::seeded(board_seed.seed);
 }
 
-fn eliminator_unlocked(map_finishes: Res<MapFinishes>) -> bool {
-    map_finishes.map3
+// Nothing is waiting to be animated, e.g matches haven't just been removed
+fn board_settled(next_board_state: Res<NextState<BoardState>>) -> bool {
+    matches!(*next_board_state, NextState::Unchanged)
 }
 
-fn bomb_unlocked(map_finishes: Res<MapFinishes>) -> bool {
-    map_finishes.map1
+// The levels that unlock each special are set in the campaign map
+fn special_unlocked(
+    special: Shape,
+) -> impl Fn(Res<CampaignMapHandle>, Res<Assets<CampaignMap>>, Res<MapFinishes>) -> bool {
+    move |campaign_map, campaign_maps, map_finishes| {
+        campaign_maps
+            .get(&campaign_map.0)
+            .is_some_and(|campaign_map| {
+                campaign_map
+                    .unlocked_specials(&map_finishes)
+                    .contains(&special)
+            })
+    }
+}
+
+fn click_allowed(settings: Res<Settings>) -> bool {
+    settings.input_mode.allows_click()
 }
 
-fn liner_unlocked(map_finishes: Res<MapFinishes>) -> bool {
-    map_finishes.map2
+fn drag_allowed(settings: Res<Settings>) -> bool {
+    settings.input_mode.allows_drag()
 }
 
 fn layout_nodes(
     board: Query<Entity, With<Board>>,
     match_counter: Query<Entity, With<MatchCounter>>,
+    level_stats_text: Query<Entity, With<LevelStatsText>>,
+    guide_text: Query<Entity, With<guide::GuideText>>,
     mut commands: Commands,
 ) {
     let mut root = commands.spawn((
@@ -115,8 +486,15 @@ fn layout_nodes(
         BoardNodeRoot,
     ));
 
+    for guide_text in guide_text.iter() {
+        root.add_child(guide_text);
+    }
     root.add_child(board.single());
     root.add_child(match_counter.single());
+    root.add_child(level_stats_text.single());
+    root.with_children(|root| {
+        spawn_settings_button(root);
+    });
 }
 
 pub fn spawn_board(mut commands: Commands) {
@@ -142,22 +520,96 @@ pub fn get_board_styling() -> (Node, BackgroundColor) {
     )
 }
 
-fn spawn_shapes_into_board(mut board: Query<Entity, With<Board>>, mut commands: Commands) {
+#[allow(clippy::too_many_arguments)]
+fn spawn_shapes_into_board(
+    mut board: Query<Entity, With<Board>>,
+    level_obstacles: Res<LevelObstacles>,
+    color_count: Res<ColorCount>,
+    shape_art: Res<ShapeArt>,
+    board_seed: Res<BoardSeed>,
+    guided: Option<Res<guide::Guided>>,
+    starting_booster: Res<StartingBooster>,
+    mut commands: Commands,
+) {
     let board = board.get_single_mut().unwrap();
 
-    for _ in 0..BOARD_TOTAL_SHAPES {
-        let spawned_shape = commands.spawn(create_shape(get_random_shape())).id();
+    if let Some(guided) = guided {
+        let shapes = guided
+            .board
+            .map(|shape| spawn_shape(&mut commands, shape, &shape_art));
+        commands.entity(board).add_children(&shapes);
+        return;
+    }
+
+    let mut rng = StdRng::seed_from_u64(board_seed.seed);
+    let layout = ObstacleLayout::new(&mut rng, &level_obstacles);
+    // Taking it from what's left keeps the layout the same with or without a booster
+    let booster_index = starting_booster.0.zip(layout.free.first().copied());
+
+    for i in 0..BOARD_TOTAL_SHAPES {
+        let spawned_shape = if let Some(obstacle) = layout.obstacle(i) {
+            spawn_shape(&mut commands, obstacle, &shape_art)
+        } else if let Some((booster, _)) = booster_index.filter(|(_, index)| *index == i) {
+            spawn_shape(&mut commands, booster, &shape_art)
+        } else if layout.chained(i) {
+            commands
+                .spawn(create_shape(
+                    get_random_shape(color_count.0, &mut rng),
+                    &shape_art,
+                ))
+                .insert((Chain, HitPoints(obstacle::CHAIN_HIT_POINTS)))
+                .id()
+        } else {
+            commands
+                .spawn(create_shape(
+                    get_random_shape(color_count.0, &mut rng),
+                    &shape_art,
+                ))
+                .id()
+        };
 
         commands.entity(board).add_child(spawned_shape);
     }
 }
 
+// Levels asking for more obstacles than fit on the board get as many as there's room for
+fn take_indices<'a>(indices: &mut &'a [usize], count: usize, obstacle: &str) -> &'a [usize] {
+    if count > indices.len() {
+        warn!(
+            "No room on the board for {count} {obstacle}, placing {}",
+            indices.len()
+        );
+    }
+
+    let (taken, rest) = indices.split_at(count.min(indices.len()));
+    *indices = rest;
+    taken
+}
+
+// Obstacles start with all their hit points
+pub fn spawn_shape(commands: &mut Commands, shape: Shape, shape_art: &ShapeArt) -> Entity {
+    let mut spawned_shape = commands.spawn(create_shape(shape, shape_art));
+
+    match shape {
+        Shape::Ice => spawned_shape.insert(HitPoints(obstacle::ICE_HIT_POINTS)),
+        Shape::Stone => spawned_shape.insert(HitPoints(obstacle::STONE_HIT_POINTS)),
+        Shape::Chocolate => spawned_shape.insert(HitPoints(hazard::CHOCOLATE_HIT_POINTS)),
+        _ => &mut spawned_shape,
+    };
+
+    spawned_shape.id()
+}
+
+#[allow(clippy::type_complexity)]
 fn write_swap_shape_event(
     mut interaction_query: Query<
         (&Interaction, Entity),
         (Changed<Interaction>, With<Button>, With<Shape>),
     >,
-    mut last_pressed_button: Local<Option<Entity>>,
+    board: Query<&Children, With<Board>>,
+    shapes: Query<&Shape>,
+    selected: Query<Entity, With<Selected>>,
+    allowed_swaps: Res<AllowedSwaps>,
     mut commands: Commands,
     mut swap_shapes_event: EventWriter<SwapShapes>,
 ) {
@@ -165,43 +617,153 @@ fn write_swap_shape_event(
         if *interaction != Interaction::Pressed {
             continue;
         }
-        match *last_pressed_button {
-            None => {
-                *last_pressed_button = Some(just_pressed_button);
-                commands.entity(just_pressed_button).insert(Outline {
-                    width: Val::Px(3.0),
-                    color: PINK_950.into(),
-                    ..default()
-                });
+        match selected.get_single() {
+            Err(_) => {
+                commands.entity(just_pressed_button).insert(Selected);
+            }
+            Ok(selected) if selected == just_pressed_button => {
+                commands.entity(selected).remove::<Selected>();
+            }
+            Ok(selected) if is_next_to(&selected, &just_pressed_button, board.single()) => {
+                let board = board.single();
+                if allowed_swaps.allows(&selected, &just_pressed_button, board, &shapes) {
+                    swap_shapes_event.send(SwapShapes(selected, just_pressed_button));
+                }
+                commands.entity(selected).remove::<Selected>();
+            }
+            Ok(selected) => {
+                commands.entity(selected).remove::<Selected>();
+                commands.entity(just_pressed_button).insert(Selected);
             }
-            Some(last_pressed_button_e) => {
-                swap_shapes_event.send(SwapShapes(last_pressed_button_e, just_pressed_button));
+        }
+    }
+}
 
-                commands
-                    .entity(last_pressed_button_e)
-                    .insert(Outline::default());
+fn cancel_selection(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    selected: Query<Entity, With<Selected>>,
+    mut commands: Commands,
+) {
+    if cancel_just_pressed(&keys, &gamepads) || mouse.just_pressed(MouseButton::Right) {
+        for selected in selected.iter() {
+            commands.entity(selected).remove::<Selected>();
+        }
+    }
+}
 
-                *last_pressed_button = None;
-            }
+// The cursor starts in the middle of the board the first time a direction is pressed
+fn move_board_cursor(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut board_cursor: ResMut<BoardCursor>,
+) {
+    let Some(direction) = direction_just_pressed(&keys, &gamepads) else {
+        return;
+    };
+
+    let middle = BOARD_SIZE as i32 / 2 + 1;
+    board_cursor.0 = Some(match board_cursor.0 {
+        Some((row, col)) => (
+            (row + direction.y).clamp(1, BOARD_SIZE as i32),
+            (col + direction.x).clamp(1, BOARD_SIZE as i32),
+        ),
+        None => (middle, middle),
+    });
+}
+
+// Shapes move around the board so the focus is moved to whatever is under the cursor
+fn focus_board_cursor(
+    board_cursor: Res<BoardCursor>,
+    board: Query<&Children, With<Board>>,
+    focused: Query<Entity, (With<Focused>, With<Shape>)>,
+    mut commands: Commands,
+) {
+    let Some((row, col)) = board_cursor.0 else {
+        return;
+    };
+
+    let Some(under_cursor) = get_entity(row, col, board.single()) else {
+        return;
+    };
+
+    if focused.get(*under_cursor).is_ok() {
+        return;
+    }
+
+    for focused in focused.iter() {
+        commands.entity(focused).remove::<Focused>();
+    }
+    commands.entity(*under_cursor).insert(Focused);
+}
+
+fn update_selected_outline(
+    newly_selected: Query<Entity, Added<Selected>>,
+    mut deselected: RemovedComponents<Selected>,
+    mut commands: Commands,
+) {
+    for entity in deselected.read() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<Outline>();
         }
     }
+
+    for entity in newly_selected.iter() {
+        commands.entity(entity).insert(Outline {
+            width: Val::Px(3.0),
+            color: PINK_950.into(),
+            ..default()
+        });
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_swap_shape_events(
     mut board_children: Query<&mut Children, With<Board>>,
     shapes: Query<&Shape>,
+    chained: Query<&Chain>,
     mut just_swapped_shapes: ResMut<JustSwappedShapes>,
     mut swap_shapes: EventReader<SwapShapes>,
+    mut move_made: EventWriter<MoveMade>,
+    mut state: ResMut<NextState<BoardState>>,
+    mut board_rng: ResMut<BoardRng>,
     mut commands: Commands,
 ) {
     for SwapShapes(button1, button2) in swap_shapes.read() {
         let mut board_children = board_children.single_mut();
 
         let is_next_to = is_next_to(button1, button2, &board_children);
-        if is_next_to {
-            swap(*button1, *button2, &mut board_children);
-            just_swapped_shapes.0 = [*button1, *button2];
+        let is_movable = [button1, button2].iter().all(|e| {
+            let shape = shapes.get(**e).unwrap();
+            !obstacle::is_immovable(shape, chained.get(**e).is_ok())
+        });
+        // Specials only go off when the swap is made, so it counts as a move
+        if !is_next_to || !is_movable {
+            continue;
+        }
+
+        let (row1, col1) = get_row_col(button1, &board_children);
+        let (row2, col2) = get_row_col(button2, &board_children);
+
+        swap(*button1, *button2, &mut board_children);
+        just_swapped_shapes.0 = [*button1, *button2];
+        move_made.send(MoveMade::default());
+
+        // Start the shapes where they were before the swap and let `animate_swaps` move them
+        let row_offset = (row1 as f32 - row2 as f32) * 100.0;
+        let col_offset = (col1 as f32 - col2 as f32) * 100.0;
+        for (shape, direction) in [(button1, 1.0), (button2, -1.0)] {
+            commands
+                .entity(*shape)
+                .insert(Swapping)
+                .entry::<Node>()
+                .and_modify(move |mut node| {
+                    node.left = Val::Percent(col_offset * direction);
+                    node.top = Val::Percent(row_offset * direction);
+                });
         }
+        state.set(BoardState::AnimatingSwap);
 
         for b in [button1, button2] {
             let entity = *b;
@@ -231,9 +793,13 @@ fn handle_swap_shape_events(
                 &mut commands,
             );
 
+            if shape == Shape::Eliminator {
+                eliminate(entity, &board_children, &mut board_rng.0, &mut commands);
+            }
+
             take_action_for_special(
-                eliminate,
-                Shape::Eliminator,
+                explode_cross,
+                Shape::Cross,
                 (shape, entity),
                 &board_children,
                 &mut commands,
@@ -253,15 +819,15 @@ fn handle_swap_shape_events(
             }
         }
 
-        fn eliminate((_, e): (Shape, Entity), board: &Children, commands: &mut Commands) {
-            remove_random_shapes(board, commands);
+        fn eliminate(e: Entity, board: &Children, rng: &mut StdRng, commands: &mut Commands) {
+            remove_random_shapes(board, rng, commands);
 
             commands.entity(e).insert(Deletion);
 
-            fn remove_random_shapes(board: &Children, commands: &mut Commands) {
+            fn remove_random_shapes(board: &Children, rng: &mut StdRng, commands: &mut Commands) {
                 let board = board.iter().collect::<Vec<_>>();
                 let random_shapes = board
-                    .choose_multiple(&mut rand::thread_rng(), BOARD_SIZE * 3)
+                    .choose_multiple(rng, BOARD_SIZE * 3)
                     .map(|e| **e)
                     .collect::<Vec<_>>();
 
@@ -370,45 +936,48 @@ fn handle_swap_shape_events(
             }
         }
 
+        fn explode_cross(
+            (_, entity): (Shape, Entity),
+            board_children: &Children,
+            commands: &mut Commands,
+        ) {
+            let (row, col) = get_row_col(&entity, board_children);
+
+            let cross_shapes = (1..=BOARD_SIZE as i32)
+                .flat_map(|i| [(row as i32, i), (i, col as i32)])
+                .filter_map(|(r, c)| get_entity(r, c, board_children));
+
+            for shape in cross_shapes {
+                commands.entity(*shape).insert(Deletion);
+            }
+        }
+
         fn swap(entity1: Entity, entity2: Entity, children: &mut Children) {
             let index1 = children.iter().position(|&e| e == entity1).unwrap();
             let index2 = children.iter().position(|&e| e == entity2).unwrap();
             children.swap(index1, index2);
         }
     }
-
-    fn is_next_to(
-        last_pressed_button: &Entity,
-        just_pressed_button: &Entity,
-        children: &Children,
-    ) -> bool {
-        let (x_1, y_1) = get_row_col(last_pressed_button, children);
-        let (x_2, y_2) = get_row_col(just_pressed_button, children);
-        let delta_x = (x_1 as i32 - x_2 as i32).abs();
-        let delta_y = (y_1 as i32 - y_2 as i32).abs();
-
-        (delta_x + delta_y) == 1
-    }
 }
 
-fn update_shape_color(
-    mut shape: Query<(&Shape, Entity), Or<(Changed<Shape>, Changed<Deletion>)>>,
-    shapes_being_deleted: Query<&Deletion>,
-    mut commands: Commands,
+#[allow(clippy::type_complexity)]
+fn update_shape_image(
+    mut shape: Query<
+        (&Shape, &mut ImageNode, Has<Deletion>),
+        Or<(Changed<Shape>, Changed<Deletion>)>,
+    >,
+    settings: Res<Settings>,
 ) {
-    for (shape, e) in shape.iter_mut() {
-        match shapes_being_deleted.get(e) {
-            Ok(_) => {
-                commands
-                    .entity(e)
-                    .entry::<BackgroundColor>()
-                    .and_modify(|mut bg| {
-                        bg.0 = Color::srgba(0.0, 0.0, 0.0, 0.0);
-                    });
-            }
-            Err(_) => {
-                commands.entity(e).insert(shape.color());
-            }
+    for (shape, mut image, deleted) in shape.iter_mut() {
+        if let Some(atlas) = &mut image.texture_atlas {
+            atlas.index = shape.atlas_index();
+        }
+
+        // Specials are replaced by their clear effect, other shapes shrink away instead
+        image.color = if deleted && shape.is_special() {
+            Color::NONE
+        } else {
+            shape.tint(settings.palette)
         };
     }
 }
@@ -542,6 +1111,25 @@ fn spawn_bombs_from_matches(
     }
 }
 
+fn spawn_crosses_from_matches(
+    board: Query<&Children, With<Board>>,
+    shape_q: Query<&Shape>,
+    deleted_shapes_q: Query<&Deletion>,
+    mut commands: Commands,
+    mut match_made: EventWriter<MatchMade>,
+) {
+    let board = board.single();
+    let cross_matches = get_cross_matches(board, &shape_q, &deleted_shapes_q);
+
+    for cross_match in cross_matches {
+        for shape in cross_match.matched_shapes {
+            commands.entity(shape).insert(Deletion);
+        }
+        commands.entity(cross_match.center).insert(Shape::Cross);
+        match_made.send(MatchMade::default());
+    }
+}
+
 fn handle_regular_matches(
     board: Query<&Children, With<Board>>,
     shape_q: Query<&Shape>,
@@ -562,44 +1150,103 @@ fn handle_regular_matches(
     }
 }
 
-fn animate_shape_bottoms_to_0(
-    mut shapes_node_q: Query<&mut Node, With<Shape>>,
+fn animate_swaps(
+    mut swapping_q: Query<(Entity, &mut Node), With<Swapping>>,
     mut state: ResMut<NextState<BoardState>>,
     time: Res<Time>,
+    settings: Res<Settings>,
+    mut commands: Commands,
 ) {
-    for mut shape in shapes_node_q.iter_mut() {
-        let delta_seconds = time.delta_secs();
-        const FALLING_SPEED: f32 = 300.0;
-        if let Val::Percent(bottom) = shape.bottom {
-            let new_percentage = bottom - delta_seconds * FALLING_SPEED;
-            shape.bottom = Val::Percent(new_percentage.max(0 as f32));
+    const SWAP_SPEED: f32 = 500.0;
+    let step = time.delta_secs() * SWAP_SPEED * settings.animation_speed;
+
+    for (_, mut node) in swapping_q.iter_mut() {
+        let node = &mut *node;
+        for inset in [&mut node.left, &mut node.top] {
+            if let Val::Percent(offset) = *inset {
+                *inset = Val::Percent(offset.signum() * (offset.abs() - step).max(0.0));
+            }
+        }
+    }
+
+    let all_shapes_have_swapped = swapping_q
+        .iter()
+        .all(|(_, node)| node.left == Val::Percent(0.0) && node.top == Val::Percent(0.0));
+
+    if all_shapes_have_swapped {
+        for (shape, mut node) in swapping_q.iter_mut() {
+            node.left = Val::Auto;
+            node.top = Val::Auto;
+            commands.entity(shape).remove::<Swapping>();
         }
+
+        state.set(BoardState::InPlay);
     }
+}
+
+fn animate_shape_bottoms_to_0(
+    mut falling_q: Query<(Entity, &mut Node, &mut Falling)>,
+    gravity: Res<Gravity>,
+    mut state: ResMut<NextState<BoardState>>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let delta_seconds = time.delta_secs() * settings.animation_speed;
+
+    for (shape, mut node, mut falling) in falling_q.iter_mut() {
+        if falling.delay > 0.0 {
+            falling.delay -= delta_seconds;
+            continue;
+        }
+
+        falling.velocity =
+            (falling.velocity + gravity.acceleration * delta_seconds).min(gravity.max_speed);
+
+        let Val::Percent(bottom) = node.bottom else {
+            panic!("Expected Val::Percent for node bottom")
+        };
 
-    let all_shapes_have_fallen = shapes_node_q.iter().all(|node| {
-        if let Val::Percent(bottom) = node.bottom {
-            0 as f32 == bottom
+        let new_percentage = bottom - falling.velocity * delta_seconds;
+        if new_percentage > 0.0 {
+            node.bottom = Val::Percent(new_percentage);
+            continue;
+        }
+
+        node.bottom = Val::Percent(0.0);
+        if falling.bounced {
+            commands.entity(shape).remove::<Falling>();
         } else {
-            panic!("Expected Val::Percent for node top")
+            falling.velocity = -falling.velocity * gravity.bounce;
+            falling.bounced = true;
         }
-    });
+    }
 
-    if all_shapes_have_fallen {
+    if falling_q.is_empty() {
         state.set(BoardState::InPlay);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_board_after_deletions(
     deleted_shapes_q: Query<Entity, With<Deletion>>,
+    immovable_q: Query<(&Shape, Has<Chain>)>,
     board_children: Query<&Children, With<Board>>,
     board: Query<Entity, With<Board>>,
+    mut ingredients_to_spawn: ResMut<ingredient::IngredientsToSpawn>,
+    color_count: Res<ColorCount>,
+    gravity: Res<Gravity>,
     mut state: ResMut<NextState<BoardState>>,
+    mut shapes_replaced: EventWriter<ShapesReplaced>,
+    mut board_rng: ResMut<BoardRng>,
     mut commands: Commands,
 ) {
-    if deleted_shapes_q.iter().count() == 0 {
+    let deleted_count = deleted_shapes_q.iter().count();
+    if deleted_count == 0 {
         return;
     } else {
         state.set(BoardState::AnimatingFallingShapes);
+        shapes_replaced.send(ShapesReplaced(deleted_count as u32));
     }
 
     let board_children = board_children.single();
@@ -607,7 +1254,14 @@ fn update_board_after_deletions(
 
     for shape in deleted_shapes_q.iter() {
         commands.entity(shape).remove::<Deletion>();
-        commands.entity(shape).insert(get_random_shape());
+        if ingredients_to_spawn.0 > 0 {
+            commands.entity(shape).insert(Shape::Ingredient);
+            ingredients_to_spawn.0 -= 1;
+        } else {
+            commands
+                .entity(shape)
+                .insert(get_random_shape(color_count.0, &mut board_rng.0));
+        }
     }
 
     let mut new_board_state = board_children.iter().copied().collect::<Vec<_>>();
@@ -618,34 +1272,79 @@ fn update_board_after_deletions(
             original_column.push(*e);
         }
 
+        // Immovable shapes stay in place and split the column into segments that fall separately
+        let is_immovable = |e: &Entity| {
+            deleted_shapes_q.get(*e).is_err()
+                && immovable_q
+                    .get(*e)
+                    .is_ok_and(|(shape, chained)| obstacle::is_immovable(shape, chained))
+        };
+
         let mut new_column: Vec<Entity> = vec![];
-        for e in original_column.clone() {
-            if deleted_shapes_q.get(e).is_ok() {
-                new_column.insert(0, e);
-            } else {
-                new_column.push(e);
-            }
+        for segment in original_column.split_inclusive(is_immovable) {
+            let (segment, immovable_shape) = match segment.split_last() {
+                Some((last, rest)) if is_immovable(last) => (rest, Some(*last)),
+                _ => (segment, None),
+            };
+
+            let delay = (col - 1) as f32 * gravity.column_delay;
+            new_column.extend(drop_segment(
+                segment,
+                delay,
+                &deleted_shapes_q,
+                &mut commands,
+            ));
+            new_column.extend(immovable_shape);
         }
 
-        for shape in original_column.clone() {
-            let original_row = original_column.iter().position(|&e| e == shape).unwrap() + 1;
-            let new_row = new_column.iter().position(|&e| e == shape).unwrap() + 1;
-            let num_of_deleted_shapes = original_column
-                .clone()
-                .iter()
-                .filter(|&e| deleted_shapes_q.get(*e).is_ok())
-                .count();
-
-            if deleted_shapes_q.get(shape).is_err() {
-                commands
-                    .entity(shape)
-                    .entry::<Node>()
+        for (shape, row) in zip(new_column, 1..=BOARD_SIZE) {
+            new_board_state[get_index(row as i32, col as i32).unwrap() as usize] = shape;
+        }
+    }
+
+    commands
+        .entity(board)
+        .replace_children(&new_board_state[..]);
+
+    fn drop_segment(
+        segment: &[Entity],
+        delay: f32,
+        deleted_shapes_q: &Query<Entity, With<Deletion>>,
+        commands: &mut Commands,
+    ) -> Vec<Entity> {
+        let mut new_segment: Vec<Entity> = vec![];
+        for e in segment {
+            if deleted_shapes_q.get(*e).is_ok() {
+                new_segment.insert(0, *e);
+            } else {
+                new_segment.push(*e);
+            }
+        }
+
+        let num_of_deleted_shapes = segment
+            .iter()
+            .filter(|&e| deleted_shapes_q.get(*e).is_ok())
+            .count();
+
+        for (original_row, shape) in segment.iter().enumerate() {
+            let new_row = new_segment.iter().position(|e| e == shape).unwrap();
+
+            if new_row != original_row || deleted_shapes_q.get(*shape).is_ok() {
+                commands
+                    .entity(*shape)
+                    .insert(Falling { delay, ..default() });
+            }
+
+            if deleted_shapes_q.get(*shape).is_err() {
+                commands
+                    .entity(*shape)
+                    .entry::<Node>()
                     .and_modify(move |mut node| {
                         node.bottom = Val::Percent((new_row as f32 - original_row as f32) * 100.0);
                     });
             } else {
                 commands
-                    .entity(shape)
+                    .entity(*shape)
                     .entry::<Node>()
                     .and_modify(move |mut node| {
                         node.bottom = Val::Percent(num_of_deleted_shapes as f32 * 100.0);
@@ -653,14 +1352,8 @@ fn update_board_after_deletions(
             }
         }
 
-        for (shape, row) in zip(new_column, 1..=BOARD_SIZE) {
-            new_board_state[get_index(row as i32, col as i32).unwrap() as usize] = shape;
-        }
+        new_segment
     }
-
-    commands
-        .entity(board)
-        .replace_children(&new_board_state[..]);
 }
 
 #[derive(Debug)]
@@ -729,9 +1422,8 @@ fn get_matches_general<const N: usize>(
             let mut entities = neighbors[..].to_vec();
             entities.push(center_entity);
 
-            let not_special = shapes.iter().all(|s| !s.is_special());
-            if all_the_same_color(&shapes[..]) && not_already_matched(&entities[..]) && not_special
-            {
+            let matchable = shapes.iter().all(|s| s.is_matchable());
+            if all_the_same_color(&shapes[..]) && not_already_matched(&entities[..]) && matchable {
                 matches.push(Match {
                     center: center_entity,
                     matched_shapes: neighbors[..].to_vec(),
@@ -771,6 +1463,36 @@ fn get_bomb_matches(
     matches
 }
 
+// Matches in a T or + shape, with the center where the lines cross
+fn get_cross_matches(
+    board: &Children,
+    shape_q: &Query<&Shape>,
+    deleted_shapes_q: &Query<&Deletion>,
+) -> Vec<Match> {
+    let above = (-1, 0);
+    let above_2 = (-2, 0);
+    let below = (1, 0);
+    let below_2 = (2, 0);
+    let left = (0, -1);
+    let left_2 = (0, -2);
+    let right = (0, 1);
+    let right_2 = (0, 2);
+
+    let get_matches = |neighbors: [(i32, i32); 4]| {
+        get_matches_general(board, shape_q, deleted_shapes_q, neighbors)
+    };
+
+    let mut matches: Vec<Match> = vec![];
+
+    matches.extend(get_matches([left, right, above, below]));
+    matches.extend(get_matches([left, right, below, below_2]));
+    matches.extend(get_matches([left, right, above, above_2]));
+    matches.extend(get_matches([above, below, right, right_2]));
+    matches.extend(get_matches([above, below, left, left_2]));
+
+    matches
+}
+
 fn get_matches_three(
     board: &Children,
     shape_q: &Query<&Shape>,
@@ -802,20 +1524,103 @@ fn reset_total_matches(mut total_matches: ResMut<TotalMatches>) {
     total_matches.0 = 0;
 }
 
-fn update_map_finishes(mut map_finishes: ResMut<MapFinishes>, current_map: Res<CurrentMap>) {
-    match current_map.get() {
-        CurrentMap::One => map_finishes.map1 = true,
-        CurrentMap::Two => map_finishes.map2 = true,
-        CurrentMap::Three => map_finishes.map3 = true,
-        CurrentMap::Four => map_finishes.map4 = true,
-        CurrentMap::None => {}
+fn reset_board_cursor(mut board_cursor: ResMut<BoardCursor>) {
+    board_cursor.0 = None;
+}
+
+// Leaving mid-animation would otherwise carry it over to the next board
+fn reset_board_state(mut board_state: ResMut<NextState<BoardState>>) {
+    board_state.set(BoardState::InPlay);
+}
+
+fn reset_level_stats(mut level_stats: ResMut<LevelStats>) {
+    *level_stats = LevelStats::default();
+}
+
+// Every shape cleared is worth points, whether it was matched or caught by a special
+fn update_level_stats(
+    time: Res<Time>,
+    mut move_made: EventReader<MoveMade>,
+    mut match_made: EventReader<MatchMade>,
+    mut shapes_replaced: EventReader<ShapesReplaced>,
+    changed_shapes: Query<&Shape, Changed<Shape>>,
+    mut level_stats: ResMut<LevelStats>,
+) {
+    let moves = move_made.read().count() as u32;
+    if moves > 0 {
+        level_stats.moves += moves;
+        level_stats.combo = 0;
+    }
+
+    if match_made.read().count() > 0 {
+        level_stats.combo += 1;
+        level_stats.best_combo = level_stats.best_combo.max(level_stats.combo);
+    }
+
+    let replaced = shapes_replaced.read().map(|r| r.0).sum::<u32>();
+    level_stats.score += replaced * POINTS_PER_SHAPE;
+    // Specials are made by turning a matched shape into one
+    level_stats.specials_created += changed_shapes.iter().filter(|s| s.is_special()).count() as u32;
+    level_stats.time_played += time.delta_secs();
+}
+
+// Replays don't add to the campaign's totals, so they match a single run through it
+fn record_campaign_stats(
+    level_stats: Res<LevelStats>,
+    first_clear: Res<FirstClear>,
+    mut campaign_stats: ResMut<CampaignStats>,
+) {
+    if first_clear.0 {
+        campaign_stats.add(&level_stats);
     }
 }
 
+fn record_best_score(
+    current_map: Res<CurrentMap>,
+    level_stats: Res<LevelStats>,
+    mut best_scores: ResMut<BestScores>,
+) {
+    let Some(level) = current_map.level() else {
+        return;
+    };
+
+    let best_score = best_scores.0.entry(level).or_default();
+    *best_score = (*best_score).max(level_stats.score);
+}
+
+fn reset_collected_ingredients(
+    mut collected_ingredients: ResMut<CollectedIngredients>,
+    mut ingredients_to_spawn: ResMut<ingredient::IngredientsToSpawn>,
+) {
+    collected_ingredients.0 = 0;
+    ingredients_to_spawn.0 = 0;
+}
+
+fn update_map_finishes(
+    mut map_finishes: ResMut<MapFinishes>,
+    current_map: Res<CurrentMap>,
+    mut first_clear: ResMut<FirstClear>,
+) {
+    let Some(level) = current_map.level() else {
+        return;
+    };
+
+    first_clear.0 = map_finishes.finish(level);
+}
+
 pub mod utils {
     use crate::board::BOARD_SIZE;
     use bevy::prelude::*;
 
+    pub fn is_next_to(shape1: &Entity, shape2: &Entity, board: &Children) -> bool {
+        let (x_1, y_1) = get_row_col(shape1, board);
+        let (x_2, y_2) = get_row_col(shape2, board);
+        let delta_x = (x_1 as i32 - x_2 as i32).abs();
+        let delta_y = (y_1 as i32 - y_2 as i32).abs();
+
+        (delta_x + delta_y) == 1
+    }
+
     pub fn get_entity(row: i32, col: i32, board: &Children) -> Option<&Entity> {
         match get_index(row, col) {
             Some(index) => board.get(index as usize),
@@ -842,60 +1647,254 @@ pub mod utils {
 
 pub mod shape {
     use bevy::{color::palettes::tailwind::*, prelude::*};
-    use rand::seq::SliceRandom;
+    use rand::{seq::SliceRandom, Rng};
+    use serde::{Deserialize, Serialize};
+
+    use super::{get_shape_styling, Deletion};
+    use crate::settings::Settings;
+
+    // Path to the atlas of shape art, with one tile per shape in the order `Shape` declares them
+    #[derive(Resource)]
+    pub struct ShapeTheme(pub String);
+
+    impl Default for ShapeTheme {
+        fn default() -> Self {
+            ShapeTheme("themes/default/shapes.png".to_string())
+        }
+    }
+
+    #[derive(Resource)]
+    pub struct ShapeArt {
+        pub image: Handle<Image>,
+        layout: Handle<TextureAtlasLayout>,
+        palette: Palette,
+    }
 
-    use super::get_shape_styling;
+    const TILE_SIZE: u32 = 64;
+    const TILE_COUNT: u32 = 16;
 
-    #[derive(Component, Reflect, Clone, Copy, PartialEq, Debug)]
-    #[require(Button, Node, BackgroundColor)]
+    impl FromWorld for ShapeArt {
+        fn from_world(world: &mut World) -> Self {
+            let theme = world.resource::<ShapeTheme>().0.clone();
+            let image = world.resource::<AssetServer>().load(theme);
+            let layout = world.resource_mut::<Assets<TextureAtlasLayout>>().add(
+                TextureAtlasLayout::from_grid(UVec2::splat(TILE_SIZE), TILE_COUNT, 1, None, None),
+            );
+
+            let palette = world.resource::<Settings>().palette;
+
+            ShapeArt {
+                image,
+                layout,
+                palette,
+            }
+        }
+    }
+
+    pub fn apply_shape_art(
+        shape_theme: Res<ShapeTheme>,
+        settings: Res<Settings>,
+        asset_server: Res<AssetServer>,
+        mut shape_art: ResMut<ShapeArt>,
+        mut images: Query<(&Shape, &mut ImageNode, Has<Deletion>)>,
+    ) {
+        shape_art.image = asset_server.load(shape_theme.0.clone());
+        shape_art.palette = settings.palette;
+
+        for (shape, mut image, deleted) in images.iter_mut() {
+            image.image = shape_art.image.clone();
+            if !deleted {
+                image.color = shape.tint(shape_art.palette);
+            }
+        }
+    }
+
+    // Colors for the colored shapes, in addition to each having its own silhouette
+    #[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+    pub enum Palette {
+        #[default]
+        Default,
+        Deuteranopia,
+        Protanopia,
+        Tritanopia,
+    }
+
+    impl Palette {
+        pub fn next(&self) -> Self {
+            match self {
+                Palette::Default => Palette::Deuteranopia,
+                Palette::Deuteranopia => Palette::Protanopia,
+                Palette::Protanopia => Palette::Tritanopia,
+                Palette::Tritanopia => Palette::Default,
+            }
+        }
+
+        pub fn name(&self) -> &'static str {
+            match self {
+                Palette::Default => "Default",
+                Palette::Deuteranopia => "Deuteranopia",
+                Palette::Protanopia => "Protanopia",
+                Palette::Tritanopia => "Tritanopia",
+            }
+        }
+
+        // Hex colors in `Shape` order: Red, Blue, Green, Pink, Orange, Purple, Yellow
+        fn hex_colors(&self) -> Option<[&'static str; 7]> {
+            match self {
+                Palette::Default => None,
+                Palette::Deuteranopia => Some([
+                    "D55E00", "0072B2", "009E73", "CC79A7", "E69F00", "56B4E9", "F0E442",
+                ]),
+                Palette::Protanopia => Some([
+                    "FE6100", "648FFF", "1BA39C", "DC267F", "FFB000", "785EF0", "F5F5F5",
+                ]),
+                Palette::Tritanopia => Some([
+                    "E8384F", "2A9DB5", "8FD14F", "FF9EC4", "8C2D1E", "4B2991", "F5F5F5",
+                ]),
+            }
+        }
+
+        pub fn color(&self, shape: Shape) -> Color {
+            match self.hex_colors() {
+                Some(colors) if shape.is_matchable() => {
+                    Srgba::hex(colors[shape.atlas_index()]).unwrap().into()
+                }
+                _ => shape.color().0,
+            }
+        }
+    }
+
+    #[derive(Component, Reflect, Clone, Copy, PartialEq, Debug, Deserialize)]
+    #[require(Button, Node, ImageNode)]
     pub enum Shape {
         Red,
         Blue,
         Green,
         Pink,
+        Orange,
+        Purple,
+        Yellow,
         Bomb,
         HorizontalLiner,
         VerticalLiner,
         Eliminator,
+        Cross,
+        Ice,
+        Stone,
+        Chocolate,
+        Ingredient,
     }
 
     impl Shape {
+        pub fn atlas_index(&self) -> usize {
+            *self as usize
+        }
+
+        // Colored shapes are drawn white and tinted, the rest are drawn in their own colors
+        pub fn tint(&self, palette: Palette) -> Color {
+            if self.is_matchable() {
+                palette.color(*self)
+            } else {
+                Color::WHITE
+            }
+        }
+
+        pub fn image(&self, shape_art: &ShapeArt) -> ImageNode {
+            ImageNode::from_atlas_image(
+                shape_art.image.clone(),
+                TextureAtlas {
+                    layout: shape_art.layout.clone(),
+                    index: self.atlas_index(),
+                },
+            )
+            .with_color(self.tint(shape_art.palette))
+        }
+
         pub fn color(&self) -> BackgroundColor {
             match self {
                 Shape::Red => RED_500.into(),
                 Shape::Blue => BLUE_500.into(),
                 Shape::Green => GREEN_500.into(),
                 Shape::Pink => PINK_500.into(),
+                Shape::Orange => ORANGE_500.into(),
+                Shape::Purple => VIOLET_300.into(),
+                Shape::Yellow => YELLOW_200.into(),
                 Shape::Bomb => GRAY_950.into(),
-                Shape::HorizontalLiner | Shape::VerticalLiner => YELLOW_500.into(),
+                Shape::HorizontalLiner | Shape::VerticalLiner => CYAN_400.into(),
                 Shape::Eliminator => PURPLE_500.into(),
+                Shape::Cross => SLATE_50.into(),
+                Shape::Ice => SKY_200.into(),
+                Shape::Stone => STONE_500.into(),
+                Shape::Chocolate => AMBER_900.into(),
+                Shape::Ingredient => AMBER_200.into(),
             }
         }
 
         pub fn is_special(&self) -> bool {
             match self {
-                Shape::Bomb | Shape::HorizontalLiner | Shape::VerticalLiner | Shape::Eliminator => {
-                    true
-                }
-                Shape::Red | Shape::Blue | Shape::Green | Shape::Pink => false,
+                Shape::Bomb
+                | Shape::HorizontalLiner
+                | Shape::VerticalLiner
+                | Shape::Eliminator
+                | Shape::Cross => true,
+                Shape::Red
+                | Shape::Blue
+                | Shape::Green
+                | Shape::Pink
+                | Shape::Orange
+                | Shape::Purple
+                | Shape::Yellow
+                | Shape::Ice
+                | Shape::Stone
+                | Shape::Chocolate
+                | Shape::Ingredient => false,
             }
         }
+
+        pub fn is_blocker(&self) -> bool {
+            matches!(self, Shape::Ice | Shape::Stone | Shape::Chocolate)
+        }
+
+        pub fn is_matchable(&self) -> bool {
+            !self.is_special() && !self.is_blocker() && *self != Shape::Ingredient
+        }
     }
 
-    pub fn get_random_shape() -> Shape {
-        let mut rng = rand::thread_rng();
-        let colors = [Shape::Red, Shape::Pink, Shape::Blue, Shape::Green];
-        let random_color = *colors.choose(&mut rng).unwrap();
+    pub const MIN_COLOR_COUNT: usize = 3;
+    pub const MAX_COLOR_COUNT: usize = 7;
+
+    // Levels with fewer colors use the first ones
+    const COLORS: [Shape; MAX_COLOR_COUNT] = [
+        Shape::Red,
+        Shape::Pink,
+        Shape::Blue,
+        Shape::Green,
+        Shape::Orange,
+        Shape::Purple,
+        Shape::Yellow,
+    ];
+
+    pub fn get_random_shape(color_count: usize, rng: &mut impl Rng) -> Shape {
+        let colors = &COLORS[..color_count.clamp(MIN_COLOR_COUNT, MAX_COLOR_COUNT)];
+        let random_color = *colors.choose(rng).unwrap();
 
         random_color
     }
 
-    pub fn create_shape(shape: Shape) -> (Shape, Button, (Node, BackgroundColor), Name) {
-        (shape, Button, get_shape_styling(shape), Name::new("Shape"))
+    pub fn create_shape(
+        shape: Shape,
+        shape_art: &ShapeArt,
+    ) -> (Shape, Button, (Node, BackgroundColor, ImageNode), Name) {
+        (
+            shape,
+            Button,
+            get_shape_styling(shape, shape_art),
+            Name::new("Shape"),
+        )
     }
 }
 
-pub fn get_shape_styling(shape: Shape) -> (Node, BackgroundColor) {
+pub fn get_shape_styling(shape: Shape, shape_art: &ShapeArt) -> (Node, BackgroundColor, ImageNode) {
     (
         Node {
             width: Val::Auto,
@@ -904,18 +1903,879 @@ pub fn get_shape_styling(shape: Shape) -> (Node, BackgroundColor) {
             bottom: Val::Percent(0.0),
             ..default()
         },
-        shape.color(),
+        BackgroundColor(Color::NONE),
+        shape.image(shape_art),
     )
 }
 
+pub mod obstacle {
+    use bevy::{color::palettes::tailwind::*, prelude::*, ui::FocusPolicy};
+
+    use super::{shape::Shape, utils::*, Board, Deletion};
+
+    pub const ICE_HIT_POINTS: u32 = 2;
+    pub const STONE_HIT_POINTS: u32 = 3;
+    pub const CHAIN_HIT_POINTS: u32 = 1;
+
+    // Locks a shape in place, it can still be matched but the match only breaks the chain
+    #[derive(Component)]
+    pub struct Chain;
+
+    #[derive(Component)]
+    pub struct HitPoints(pub u32);
+
+    pub fn is_immovable(shape: &Shape, chained: bool) -> bool {
+        shape.is_blocker() || chained
+    }
+
+    // Ice and chocolate are hit by deletions on them or next to them, stone only by deletions on
+    // it (specials) and chains by their shape being matched.
+    pub fn damage(
+        board: Query<&Children, With<Board>>,
+        deleted_shapes_q: Query<&Deletion>,
+        mut obstacles: Query<(Entity, &Shape, &mut HitPoints, Has<Chain>)>,
+        mut commands: Commands,
+    ) {
+        let board = board.single();
+
+        for (entity, shape, mut hit_points, chained) in obstacles.iter_mut() {
+            let deleted = deleted_shapes_q.get(entity).is_ok();
+
+            let hit = match shape {
+                Shape::Ice | Shape::Chocolate => {
+                    deleted || next_to_deletion(&entity, board, &deleted_shapes_q)
+                }
+                _ => deleted,
+            };
+
+            if !hit {
+                continue;
+            }
+
+            hit_points.0 = hit_points.0.saturating_sub(1);
+
+            if chained {
+                commands.entity(entity).remove::<Deletion>();
+                if hit_points.0 == 0 {
+                    commands.entity(entity).remove::<(Chain, HitPoints)>();
+                }
+            } else if hit_points.0 == 0 {
+                commands
+                    .entity(entity)
+                    .remove::<HitPoints>()
+                    .insert(Deletion);
+            } else {
+                commands.entity(entity).remove::<Deletion>();
+            }
+        }
+
+        fn next_to_deletion(
+            entity: &Entity,
+            board: &Children,
+            deleted_shapes_q: &Query<&Deletion>,
+        ) -> bool {
+            let (row, col) = get_row_col(entity, board);
+            let (row, col) = (row as i32, col as i32);
+
+            [
+                (row - 1, col),
+                (row + 1, col),
+                (row, col - 1),
+                (row, col + 1),
+            ]
+            .iter()
+            .filter_map(|(r, c)| get_entity(*r, *c, board))
+            .any(|e| deleted_shapes_q.get(*e).is_ok())
+        }
+    }
+
+    pub fn update_visuals(
+        changed_hit_points: Query<(Entity, &HitPoints, Has<Chain>), Changed<HitPoints>>,
+        mut removed_hit_points: RemovedComponents<HitPoints>,
+        mut commands: Commands,
+    ) {
+        for (entity, hit_points, chained) in changed_hit_points.iter() {
+            let hit_points = hit_points.0;
+            commands
+                .entity(entity)
+                .despawn_descendants()
+                .with_child((
+                    Text::new(hit_points.to_string()),
+                    TextColor(GRAY_950.into()),
+                    FocusPolicy::Pass,
+                    Node {
+                        margin: UiRect::all(Val::Auto),
+                        ..default()
+                    },
+                    Name::new("Hit Points Text"),
+                ))
+                .entry::<Node>()
+                .and_modify(move |mut node| {
+                    node.border = if chained {
+                        UiRect::all(Val::Px(4.))
+                    } else {
+                        UiRect::ZERO
+                    };
+                });
+
+            if chained {
+                commands.entity(entity).insert(BorderColor(GRAY_400.into()));
+            }
+        }
+
+        for entity in removed_hit_points.read() {
+            if let Some(mut entity) = commands.get_entity(entity) {
+                entity
+                    .despawn_descendants()
+                    .remove::<BorderColor>()
+                    .entry::<Node>()
+                    .and_modify(|mut node| {
+                        node.border = UiRect::ZERO;
+                    });
+            }
+        }
+    }
+}
+
+mod hazard {
+    use bevy::prelude::*;
+    use rand::prelude::*;
+
+    use super::{
+        obstacle::{Chain, HitPoints},
+        shape::Shape,
+        utils::*,
+        Board, BoardRng, BoardState, Deletion, MoveMade,
+    };
+
+    pub const CHOCOLATE_HIT_POINTS: u32 = 1;
+
+    // Chocolate spreads to a neighbouring shape after every move that didn't destroy any of it
+    #[derive(Resource, Default)]
+    pub struct HazardTurn {
+        move_pending: bool,
+        hazard_destroyed: bool,
+    }
+
+    pub fn reset(mut hazard_turn: ResMut<HazardTurn>) {
+        *hazard_turn = HazardTurn::default();
+    }
+
+    // Runs every tick, not just in `BoardState::InPlay`, so that moves aren't missed while the
+    // swap animates
+    pub fn track_moves(mut move_made: EventReader<MoveMade>, mut hazard_turn: ResMut<HazardTurn>) {
+        for _move_made in move_made.read() {
+            hazard_turn.move_pending = true;
+            hazard_turn.hazard_destroyed = false;
+        }
+    }
+
+    pub fn track_destroyed(
+        deleted_shapes_q: Query<&Shape, With<Deletion>>,
+        mut hazard_turn: ResMut<HazardTurn>,
+    ) {
+        if deleted_shapes_q.iter().any(|s| *s == Shape::Chocolate) {
+            hazard_turn.hazard_destroyed = true;
+        }
+    }
+
+    // Runs once the board has settled after a move, e.g no more cascades are falling
+    pub fn grow(
+        board: Query<&Children, With<Board>>,
+        shapes: Query<(&Shape, Has<Chain>)>,
+        next_board_state: Res<NextState<BoardState>>,
+        mut hazard_turn: ResMut<HazardTurn>,
+        mut board_rng: ResMut<BoardRng>,
+        mut commands: Commands,
+    ) {
+        let board_settled = super::board_settled(next_board_state);
+        if !hazard_turn.move_pending || !board_settled {
+            return;
+        }
+
+        hazard_turn.move_pending = false;
+        if hazard_turn.hazard_destroyed {
+            return;
+        }
+
+        let board = board.single();
+
+        let growable_shapes = board
+            .iter()
+            .filter(|e| shapes.get(**e).is_ok_and(|(s, _)| *s == Shape::Chocolate))
+            .flat_map(|chocolate| {
+                let (row, col) = get_row_col(chocolate, board);
+                let (row, col) = (row as i32, col as i32);
+
+                [
+                    (row - 1, col),
+                    (row + 1, col),
+                    (row, col - 1),
+                    (row, col + 1),
+                ]
+                .into_iter()
+                .filter_map(|(r, c)| get_entity(r, c, board))
+            })
+            .filter(|e| {
+                shapes
+                    .get(**e)
+                    .is_ok_and(|(s, chained)| s.is_matchable() && !chained)
+            })
+            .collect::<Vec<_>>();
+
+        // Drawn from the board's rng so a replayed layout spreads the same way
+        if let Some(shape) = growable_shapes.choose(&mut board_rng.0) {
+            commands
+                .entity(**shape)
+                .insert((Shape::Chocolate, HitPoints(CHOCOLATE_HIT_POINTS)));
+        }
+    }
+}
+
+mod ingredient {
+    use bevy::prelude::*;
+
+    use super::{shape::Shape, utils::*, Board, Deletion, BOARD_SIZE};
+    use crate::{CollectedIngredients, LevelObstacles, Objective};
+
+    // How many of the shapes refilled next should be ingredients
+    #[derive(Resource, Default)]
+    pub struct IngredientsToSpawn(pub usize);
+
+    // Ingredients can't be destroyed, they're only removed by reaching the bottom row
+    pub fn collect(
+        board: Query<&Children, With<Board>>,
+        ingredients: Query<(Entity, &Shape)>,
+        level_obstacles: Res<LevelObstacles>,
+        objective: Res<Objective>,
+        mut collected_ingredients: ResMut<CollectedIngredients>,
+        mut ingredients_to_spawn: ResMut<IngredientsToSpawn>,
+        mut commands: Commands,
+    ) {
+        let board = board.single();
+
+        let mut ingredients_on_board = 0;
+        for (entity, _) in ingredients.iter().filter(|(_, s)| **s == Shape::Ingredient) {
+            let (row, _) = get_row_col(&entity, board);
+
+            if row == BOARD_SIZE {
+                commands.entity(entity).insert(Deletion);
+                collected_ingredients.0 += 1;
+            } else {
+                commands.entity(entity).remove::<Deletion>();
+                ingredients_on_board += 1;
+            }
+        }
+
+        let Objective::CollectIngredients(needed_ingredients) = *objective else {
+            ingredients_to_spawn.0 = 0;
+            return;
+        };
+
+        let ingredients_left = (needed_ingredients as usize)
+            .saturating_sub(collected_ingredients.0 as usize + ingredients_on_board);
+
+        ingredients_to_spawn.0 = level_obstacles
+            .ingredients
+            .saturating_sub(ingredients_on_board)
+            .min(ingredients_left);
+    }
+}
+
+mod hint {
+    use bevy::prelude::*;
+    use rand::prelude::*;
+
+    use super::{
+        obstacle::{self, Chain},
+        shape::Shape,
+        Board, MoveMade, BOARD_SIZE,
+    };
+    use crate::settings::Settings;
+
+    #[derive(Component)]
+    pub struct Hinted;
+
+    // How long the board has been waiting on a move
+    #[derive(Resource, Default)]
+    pub struct IdleTime(f32);
+
+    pub fn reset(mut idle_time: ResMut<IdleTime>) {
+        idle_time.0 = 0.0;
+    }
+
+    pub fn show(
+        time: Res<Time>,
+        settings: Res<Settings>,
+        board: Query<&Children, With<Board>>,
+        shapes: Query<(&Shape, Has<Chain>)>,
+        hinted: Query<(), With<Hinted>>,
+        mut idle_time: ResMut<IdleTime>,
+        mut commands: Commands,
+    ) {
+        let Some(hint_delay) = settings.hint_delay else {
+            return;
+        };
+
+        idle_time.0 += time.delta_secs();
+        if idle_time.0 < hint_delay || !hinted.is_empty() {
+            return;
+        }
+
+        if let Some(possible_move) = find_move(board.single(), &shapes) {
+            for shape in possible_move {
+                commands.entity(shape).insert(Hinted);
+            }
+        }
+    }
+
+    pub fn clear(
+        mut move_made: EventReader<MoveMade>,
+        mut hinted: Query<(Entity, &mut Transform), With<Hinted>>,
+        mut idle_time: ResMut<IdleTime>,
+        mut commands: Commands,
+    ) {
+        if move_made.read().count() == 0 {
+            return;
+        }
+
+        idle_time.0 = 0.0;
+        for (entity, mut transform) in hinted.iter_mut() {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<Hinted>();
+        }
+    }
+
+    pub fn pulse(time: Res<Time>, mut hinted: Query<&mut Transform, With<Hinted>>) {
+        let scale = 1.0 + 0.08 * (time.elapsed_secs() * 6.0).sin();
+        for mut transform in hinted.iter_mut() {
+            transform.scale = Vec3::splat(scale);
+        }
+    }
+
+    // A random pair of neighbors that would make a match if swapped, or that would set off a
+    // special
+    fn find_move(board: &Children, shapes: &Query<(&Shape, Has<Chain>)>) -> Option<[Entity; 2]> {
+        let cells = board
+            .iter()
+            .map(|e| *shapes.get(*e).unwrap().0)
+            .collect::<Vec<_>>();
+        let movable = |i: usize| {
+            let (shape, chained) = shapes.get(board[i]).unwrap();
+            !obstacle::is_immovable(shape, chained)
+        };
+
+        let mut moves = vec![];
+        for i in 0..cells.len() {
+            let (row, col) = (i / BOARD_SIZE, i % BOARD_SIZE);
+            let right = (col + 1 < BOARD_SIZE).then_some(i + 1);
+            let below = (row + 1 < BOARD_SIZE).then_some(i + BOARD_SIZE);
+
+            for j in [right, below].into_iter().flatten() {
+                if !movable(i) || !movable(j) {
+                    continue;
+                }
+
+                let mut swapped = cells.clone();
+                swapped.swap(i, j);
+                if cells[i].is_special() || cells[j].is_special() || has_match(&swapped) {
+                    moves.push([board[i], board[j]]);
+                }
+            }
+        }
+
+        moves.choose(&mut rand::thread_rng()).copied()
+    }
+
+    fn has_match(cells: &[Shape]) -> bool {
+        let at = |row: usize, col: usize| cells[row * BOARD_SIZE + col];
+        let three_of = |a: Shape, b: Shape, c: Shape| a.is_matchable() && a == b && b == c;
+
+        (0..BOARD_SIZE).any(|row| {
+            (0..BOARD_SIZE - 2).any(|col| {
+                three_of(at(row, col), at(row, col + 1), at(row, col + 2))
+                    || three_of(at(col, row), at(col + 1, row), at(col + 2, row))
+            })
+        })
+    }
+}
+
+mod guide {
+    use bevy::prelude::*;
+
+    use super::{
+        hint::Hinted, shape::Shape, utils::*, AllowedSwaps, Board, BoardRng, MoveMade, BOARD_SIZE,
+        TUTORIAL_SEED,
+    };
+    use crate::{
+        settings::Settings,
+        tutorial::{Guide, GuideHandle, GuideStep},
+        CurrentMap, MapFinishes,
+    };
+
+    const DIMMED_ALPHA: f32 = 0.25;
+
+    // The level is being played with a guide, one step at a time
+    #[derive(Resource)]
+    pub struct Guided {
+        pub board: [Shape; BOARD_SIZE * BOARD_SIZE],
+        steps: Vec<GuideStep>,
+        outro: String,
+        step: usize,
+        // The current step has been pointed out
+        shown: bool,
+    }
+
+    #[derive(Component)]
+    pub struct GuideText;
+
+    // Guides are only for levels that haven't been won yet
+    pub fn start(
+        current_map: Res<CurrentMap>,
+        map_finishes: Res<MapFinishes>,
+        guide_handle: Res<GuideHandle>,
+        guides: Res<Assets<Guide>>,
+        mut commands: Commands,
+    ) {
+        let guide = guides.get(&guide_handle.0).filter(|guide| {
+            current_map.level() == Some(guide.level) && !map_finishes.finished(guide.level)
+        });
+
+        let Some(guide) = guide else {
+            return;
+        };
+
+        commands.insert_resource(BoardRng::seeded(TUTORIAL_SEED));
+        commands.insert_resource(Guided {
+            board: guide.board,
+            steps: guide.steps.clone(),
+            outro: guide.outro.clone(),
+            step: 0,
+            shown: false,
+        });
+        commands.spawn((GuideText, Text::new(""), Name::new("Guide Text")));
+    }
+
+    pub fn stop(mut allowed_swaps: ResMut<AllowedSwaps>, mut commands: Commands) {
+        *allowed_swaps = AllowedSwaps::Any;
+        commands.remove_resource::<Guided>();
+    }
+
+    // Only the two shapes to swap are left bright, and nothing can be swapped until the board has
+    // settled and the next step is pointed out
+    pub fn advance(
+        mut move_made: EventReader<MoveMade>,
+        mut shapes: Query<(&Shape, &mut ImageNode)>,
+        settings: Res<Settings>,
+        mut guided: ResMut<Guided>,
+        mut allowed_swaps: ResMut<AllowedSwaps>,
+    ) {
+        let moves = move_made.read().count();
+        if moves == 0 {
+            return;
+        }
+
+        guided.step += moves;
+        guided.shown = false;
+        *allowed_swaps = AllowedSwaps::None;
+
+        for (shape, mut image) in shapes.iter_mut() {
+            image.color = shape.tint(settings.palette);
+        }
+    }
+
+    pub fn point_out(
+        board: Query<&Children, With<Board>>,
+        mut shapes: Query<(&Shape, &mut ImageNode)>,
+        settings: Res<Settings>,
+        mut guided: ResMut<Guided>,
+        mut allowed_swaps: ResMut<AllowedSwaps>,
+        mut guide_text: Query<&mut Text, With<GuideText>>,
+        mut commands: Commands,
+    ) {
+        if guided.shown {
+            return;
+        }
+        guided.shown = true;
+
+        let Some(step) = guided.steps.get(guided.step) else {
+            // Done, the rest of the level is up to the player
+            guide_text.single_mut().0 = guided.outro.clone();
+            *allowed_swaps = AllowedSwaps::Any;
+            commands.remove_resource::<Guided>();
+            return;
+        };
+
+        let board = board.single();
+        let ((row1, col1), (row2, col2)) = step.swap;
+        let to_swap = [get_entity(row1, col1, board), get_entity(row2, col2, board)];
+
+        for shape in board.iter() {
+            let Ok((kind, mut image)) = shapes.get_mut(*shape) else {
+                continue;
+            };
+
+            if to_swap.contains(&Some(shape)) {
+                commands.entity(*shape).insert(Hinted);
+            } else {
+                image.color = kind.tint(settings.palette).with_alpha(DIMMED_ALPHA);
+            }
+        }
+
+        guide_text.single_mut().0 = step.text.clone();
+        *allowed_swaps = AllowedSwaps::Between(step.swap.0, step.swap.1);
+    }
+}
+
+mod drag {
+    use bevy::{prelude::*, window::PrimaryWindow};
+
+    use super::{
+        obstacle::{self, Chain},
+        shape::Shape,
+        utils::*,
+        AllowedSwaps, Board, Selected, SwapShapes,
+    };
+
+    // How far, as a fraction of a shape's size, it has to be dragged to swap
+    const SWAP_THRESHOLD: f32 = 0.3;
+
+    #[derive(Resource, Default)]
+    pub struct Drag(Option<DraggedShape>);
+
+    pub struct DraggedShape {
+        shape: Entity,
+        start: Vec2,
+        current: Vec2,
+    }
+
+    fn pointer_position(window: &Window, touches: &Touches) -> Option<Vec2> {
+        touches
+            .first_pressed_position()
+            .or_else(|| window.cursor_position())
+    }
+
+    // A press next to the selected shape is a click swap, made by `write_swap_shape_event`. This
+    // only runs once the board has settled so a click swap made earlier in the frame isn't
+    // followed by a drag of the same press.
+    pub fn start(
+        pressed_shapes: Query<(Entity, &Interaction, &Shape, Has<Chain>), Changed<Interaction>>,
+        board: Query<&Children, With<Board>>,
+        selected: Query<Entity, With<Selected>>,
+        window: Query<&Window, With<PrimaryWindow>>,
+        mouse: Res<ButtonInput<MouseButton>>,
+        touches: Res<Touches>,
+        mut drag: ResMut<Drag>,
+    ) {
+        // Shapes pressed with the keyboard or a gamepad can't be dragged
+        if !mouse.pressed(MouseButton::Left) && touches.first_pressed_position().is_none() {
+            return;
+        }
+
+        let board = board.single();
+        for (shape_e, interaction, shape, chained) in pressed_shapes.iter() {
+            if *interaction != Interaction::Pressed || obstacle::is_immovable(shape, chained) {
+                continue;
+            }
+
+            let finishes_click_swap = selected
+                .iter()
+                .any(|selected| is_next_to(&selected, &shape_e, board));
+            if finishes_click_swap {
+                continue;
+            }
+
+            let Some(position) = pointer_position(window.single(), &touches) else {
+                continue;
+            };
+
+            drag.0 = Some(DraggedShape {
+                shape: shape_e,
+                start: position,
+                current: position,
+            });
+        }
+    }
+
+    // Moves the shape along whichever axis it's dragged the most, up to one shape away
+    pub fn follow(
+        window: Query<&Window, With<PrimaryWindow>>,
+        touches: Res<Touches>,
+        mut shapes: Query<(&mut Node, &ComputedNode)>,
+        mut drag: ResMut<Drag>,
+        mut commands: Commands,
+    ) {
+        let Some(dragged) = drag.0.as_mut() else {
+            return;
+        };
+
+        if let Some(position) = pointer_position(window.single(), &touches) {
+            dragged.current = position;
+        }
+
+        let Ok((mut node, computed_node)) = shapes.get_mut(dragged.shape) else {
+            return;
+        };
+
+        let max_offset = computed_node.size() * computed_node.inverse_scale_factor();
+        let delta = dragged.current - dragged.start;
+
+        if delta.x.abs() > delta.y.abs() {
+            node.left = Val::Px(delta.x.clamp(-max_offset.x, max_offset.x));
+            node.top = Val::Auto;
+        } else {
+            node.left = Val::Auto;
+            node.top = Val::Px(delta.y.clamp(-max_offset.y, max_offset.y));
+        }
+
+        commands.entity(dragged.shape).insert(ZIndex(1));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn end(
+        mouse: Res<ButtonInput<MouseButton>>,
+        touches: Res<Touches>,
+        board: Query<&Children, With<Board>>,
+        mut shapes: Query<(&mut Node, &ComputedNode)>,
+        shape_kinds: Query<&Shape>,
+        selected: Query<Entity, With<Selected>>,
+        allowed_swaps: Res<AllowedSwaps>,
+        mut drag: ResMut<Drag>,
+        mut swap_shapes: EventWriter<SwapShapes>,
+        mut commands: Commands,
+    ) {
+        if !mouse.just_released(MouseButton::Left) && !touches.any_just_released() {
+            return;
+        }
+
+        let Some(dragged) = drag.0.take() else {
+            return;
+        };
+
+        let Ok((mut node, computed_node)) = shapes.get_mut(dragged.shape) else {
+            return;
+        };
+
+        node.left = Val::Auto;
+        node.top = Val::Auto;
+        commands.entity(dragged.shape).remove::<ZIndex>();
+
+        let size = computed_node.size() * computed_node.inverse_scale_factor();
+        let delta = (dragged.current - dragged.start) / size;
+
+        // Too short to be a drag, it's a click and is handled by `write_swap_shape_event`
+        if delta.abs().max_element() < SWAP_THRESHOLD {
+            return;
+        }
+
+        let board = board.single();
+        let (row, col) = get_row_col(&dragged.shape, board);
+        let (row, col) = (row as i32, col as i32);
+
+        let neighbor = if delta.x.abs() > delta.y.abs() {
+            get_entity(row, col + delta.x.signum() as i32, board)
+        } else {
+            get_entity(row + delta.y.signum() as i32, col, board)
+        };
+
+        if let Some(neighbor) = neighbor {
+            if allowed_swaps.allows(&dragged.shape, neighbor, board, &shape_kinds) {
+                swap_shapes.send(SwapShapes(dragged.shape, *neighbor));
+            }
+        }
+
+        for selected in selected.iter() {
+            commands.entity(selected).remove::<Selected>();
+        }
+    }
+}
+
+mod clear_effects {
+    use bevy::{color::palettes::tailwind::*, prelude::*};
+    use rand::prelude::*;
+
+    use super::{shape::Shape, BoardState, Deletion, BOARD_SIZE};
+    use crate::settings::Settings;
+
+    const CLEAR_DURATION: f32 = 0.35;
+    const SPARKLE_COUNT: usize = 8;
+
+    #[derive(Resource)]
+    pub struct ClearTimer(Timer);
+
+    impl Default for ClearTimer {
+        fn default() -> Self {
+            ClearTimer(Timer::from_seconds(CLEAR_DURATION, TimerMode::Once))
+        }
+    }
+
+    // A shape that's shrinking and popping before being refilled
+    #[derive(Component)]
+    pub struct Clearing;
+
+    // Spawned as a child of the special that set it off
+    #[derive(Component)]
+    pub enum ClearEffect {
+        Shockwave,
+        Beam { horizontal: bool },
+        Sparkle(Vec2),
+    }
+
+    pub fn start(
+        deleted_shapes_q: Query<(Entity, &Shape), With<Deletion>>,
+        mut clear_timer: ResMut<ClearTimer>,
+        mut state: ResMut<NextState<BoardState>>,
+        mut commands: Commands,
+    ) {
+        if deleted_shapes_q.is_empty() {
+            return;
+        }
+
+        state.set(BoardState::AnimatingClear);
+        clear_timer.0.reset();
+
+        for (entity, shape) in deleted_shapes_q.iter() {
+            let effects = match shape {
+                Shape::Bomb => vec![ClearEffect::Shockwave],
+                Shape::HorizontalLiner => vec![ClearEffect::Beam { horizontal: true }],
+                Shape::VerticalLiner => vec![ClearEffect::Beam { horizontal: false }],
+                Shape::Cross => vec![
+                    ClearEffect::Beam { horizontal: true },
+                    ClearEffect::Beam { horizontal: false },
+                ],
+                Shape::Eliminator => {
+                    let mut rng = rand::thread_rng();
+                    (0..SPARKLE_COUNT)
+                        .map(|_| {
+                            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                            ClearEffect::Sparkle(Vec2::from_angle(angle))
+                        })
+                        .collect()
+                }
+                _ => {
+                    commands.entity(entity).insert(Clearing);
+                    vec![]
+                }
+            };
+
+            commands.entity(entity).with_children(|parent| {
+                for effect in effects {
+                    parent.spawn((
+                        Node {
+                            position_type: PositionType::Absolute,
+                            ..default()
+                        },
+                        GlobalZIndex(1),
+                        Name::new("Clear Effect"),
+                        effect,
+                    ));
+                }
+            });
+        }
+    }
+
+    pub fn animate(
+        time: Res<Time>,
+        settings: Res<Settings>,
+        mut clear_timer: ResMut<ClearTimer>,
+        mut clearing_q: Query<&mut Transform, With<Clearing>>,
+        mut effects_q: Query<(&ClearEffect, &mut Node, Entity)>,
+        mut commands: Commands,
+    ) {
+        clear_timer
+            .0
+            .tick(time.delta().mul_f32(settings.animation_speed));
+        let progress = clear_timer.0.fraction();
+
+        // Grows a little before shrinking away
+        let pop_scale = if progress < 0.3 {
+            1.0 + progress / 0.3 * 0.2
+        } else {
+            1.2 * (1.0 - (progress - 0.3) / 0.7)
+        };
+        for mut transform in clearing_q.iter_mut() {
+            transform.scale = Vec3::splat(pop_scale);
+        }
+
+        let fade = |color: Srgba| Color::from(color.with_alpha(1.0 - progress));
+
+        for (effect, mut node, entity) in effects_q.iter_mut() {
+            match effect {
+                ClearEffect::Shockwave => {
+                    let size = 100.0 + progress * 200.0;
+                    node.width = Val::Percent(size);
+                    node.height = Val::Percent(size);
+                    node.left = Val::Percent(-(size - 100.0) / 2.0);
+                    node.top = Val::Percent(-(size - 100.0) / 2.0);
+                    node.border = UiRect::all(Val::Px(6.0));
+                    commands
+                        .entity(entity)
+                        .insert((BorderColor(fade(GRAY_50)), BorderRadius::MAX));
+                }
+                ClearEffect::Beam { horizontal } => {
+                    let length = 100.0 + progress * (BOARD_SIZE as f32 * 2.0 - 1.0) * 100.0;
+                    let thickness = 40.0 * (1.0 - progress);
+                    let (width, height) = if *horizontal {
+                        (length, thickness)
+                    } else {
+                        (thickness, length)
+                    };
+                    node.width = Val::Percent(width);
+                    node.height = Val::Percent(height);
+                    node.left = Val::Percent(-(width - 100.0) / 2.0);
+                    node.top = Val::Percent(-(height - 100.0) / 2.0);
+                    commands
+                        .entity(entity)
+                        .insert(BackgroundColor(fade(CYAN_200)));
+                }
+                ClearEffect::Sparkle(direction) => {
+                    let distance = progress * 150.0;
+                    node.width = Val::Percent(15.0);
+                    node.height = Val::Percent(15.0);
+                    node.left = Val::Percent(42.5 + direction.x * distance);
+                    node.top = Val::Percent(42.5 + direction.y * distance);
+                    commands
+                        .entity(entity)
+                        .insert(BackgroundColor(fade(FUCHSIA_200)));
+                }
+            }
+        }
+    }
+
+    pub fn finished(clear_timer: Res<ClearTimer>) -> bool {
+        clear_timer.0.finished()
+    }
+
+    pub fn finish(
+        mut clearing_q: Query<(Entity, &mut Transform), With<Clearing>>,
+        effects_q: Query<Entity, With<ClearEffect>>,
+        mut commands: Commands,
+    ) {
+        for (entity, mut transform) in clearing_q.iter_mut() {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<Clearing>();
+        }
+
+        for effect in effects_q.iter() {
+            commands.entity(effect).despawn_recursive();
+        }
+    }
+}
+
 mod match_counter {
     use bevy::{color::palettes::css::WHITE, prelude::*};
 
-    use crate::{NeededMatches, TotalMatches};
+    use super::shape::Shape;
+    use crate::{
+        CollectedIngredients, LevelStats, MoveLimit, NeededMatches, Objective, TotalMatches,
+    };
 
     #[derive(Component)]
     pub struct MatchCounter;
 
+    #[derive(Component)]
+    pub struct LevelStatsText;
+
     pub fn spawn(mut commands: Commands) {
         commands.spawn((
             MatchCounter,
@@ -926,14 +2786,124 @@ mod match_counter {
             },
             TextColor(WHITE.into()),
         ));
+        commands.spawn((LevelStatsText, Text::new("")));
+    }
+
+    pub fn update_level_stats_text(
+        level_stats: Res<LevelStats>,
+        move_limit: Res<MoveLimit>,
+        mut level_stats_text: Query<&mut Text, With<LevelStatsText>>,
+    ) {
+        let moves = match move_limit.0 {
+            Some(move_limit) => format!("{}/{move_limit}", level_stats.moves),
+            None => level_stats.moves.to_string(),
+        };
+        for mut text in level_stats_text.iter_mut() {
+            text.0 = format!("Moves: {moves}  Score: {}", level_stats.score);
+        }
     }
 
     pub fn update(
         total_matches: Res<TotalMatches>,
         mut match_counter_text: Query<&mut Text, With<MatchCounter>>,
         needed_matches: Res<NeededMatches>,
+        objective: Res<Objective>,
+        collected_ingredients: Res<CollectedIngredients>,
+        shapes: Query<&Shape>,
     ) {
+        let count = |shape: Shape| shapes.iter().filter(|s| **s == shape).count();
+
         let mut text = match_counter_text.single_mut();
-        text.0 = total_matches.0.to_string() + "/" + &needed_matches.0.to_string();
+        text.0 = match *objective {
+            Objective::Matches => total_matches.0.to_string() + "/" + &needed_matches.0.to_string(),
+            Objective::ClearIce => count(Shape::Ice).to_string() + " ice left",
+            Objective::ClearStone => count(Shape::Stone).to_string() + " stone left",
+            Objective::CollectIngredients(needed_ingredients) => {
+                collected_ingredients.0.to_string() + "/" + &needed_ingredients.to_string()
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::{RunSystemOnce, SystemState};
+
+    use super::*;
+
+    // A board of red shapes, apart from those placed at (row, col)
+    fn board_world(placed: &[((i32, i32), Shape)]) -> (World, Entity) {
+        let mut world = World::new();
+        let board = world
+            .spawn(Board)
+            .with_children(|board| {
+                for index in 0..BOARD_SIZE * BOARD_SIZE {
+                    let shape = placed
+                        .iter()
+                        .find(|((row, col), _)| get_index(*row, *col) == Some(index as i32))
+                        .map_or(Shape::Red, |(_, shape)| *shape);
+                    board.spawn(shape);
+                }
+            })
+            .id();
+
+        (world, board)
+    }
+
+    fn allows(allowed_swaps: AllowedSwaps, a: (i32, i32), b: (i32, i32)) -> bool {
+        let (mut world, board) = board_world(&[((3, 3), Shape::Bomb)]);
+        let mut shapes: SystemState<Query<&Shape>> = SystemState::new(&mut world);
+        let shapes = shapes.get(&world);
+        let children = world.get::<Children>(board).unwrap();
+
+        allowed_swaps.allows(
+            get_entity(a.0, a.1, children).unwrap(),
+            get_entity(b.0, b.1, children).unwrap(),
+            children,
+            &shapes,
+        )
+    }
+
+    #[test]
+    fn any_and_none_ignore_the_shapes() {
+        assert!(allows(AllowedSwaps::Any, (1, 1), (1, 2)));
+        assert!(!allows(AllowedSwaps::None, (1, 1), (1, 2)));
+    }
+
+    #[test]
+    fn between_allows_either_order_of_the_two_positions() {
+        let between = AllowedSwaps::Between((2, 1), (2, 2));
+
+        assert!(allows(between, (2, 1), (2, 2)));
+        assert!(allows(between, (2, 2), (2, 1)));
+        assert!(!allows(between, (2, 2), (2, 3)));
+        assert!(!allows(between, (1, 1), (2, 1)));
+    }
+
+    #[test]
+    fn with_special_needs_a_special_on_either_side() {
+        assert!(allows(AllowedSwaps::WithSpecial, (3, 3), (3, 4)));
+        assert!(allows(AllowedSwaps::WithSpecial, (2, 3), (3, 3)));
+        assert!(!allows(AllowedSwaps::WithSpecial, (1, 1), (1, 2)));
+    }
+
+    #[test]
+    fn specials_swapped_with_an_obstacle_dont_go_off() {
+        let (mut world, board) = board_world(&[((3, 3), Shape::Bomb), ((3, 4), Shape::Stone)]);
+        world.init_resource::<Events<SwapShapes>>();
+        world.init_resource::<Events<MoveMade>>();
+        world.init_resource::<NextState<BoardState>>();
+        world.insert_resource(JustSwappedShapes([Entity::PLACEHOLDER; 2]));
+        world.insert_resource(BoardRng::seeded(TUTORIAL_SEED));
+
+        let children = world.get::<Children>(board).unwrap();
+        let bomb = *get_entity(3, 3, children).unwrap();
+        let stone = *get_entity(3, 4, children).unwrap();
+        world.send_event(SwapShapes(bomb, stone));
+        world.run_system_once(handle_swap_shape_events).unwrap();
+
+        let mut deleted = world.query_filtered::<(), With<Deletion>>();
+        assert_eq!(deleted.iter(&world).count(), 0);
+        assert!(world.resource::<Events<MoveMade>>().is_empty());
     }
 }
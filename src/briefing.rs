@@ -0,0 +1,357 @@
+use bevy::{color::palettes::tailwind::GRAY_400, prelude::*};
+
+use crate::{
+    board::{
+        shape::{Shape, ShapeArt},
+        BoardSeed, ObstacleLayout, StartingBooster, BOARD_SIZE,
+    },
+    focus::{cancel_just_pressed, Focusable, Focused},
+    map::{CampaignMap, CampaignMapHandle},
+    tutorial::guide_loaded,
+    BestScores, CurrentMap, GameState, LevelObstacles, MapFinishes, MoveLimit, NeededMatches,
+    Objective,
+};
+
+#[derive(Component)]
+struct Briefing;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum BriefingButton {
+    Back,
+    Play,
+}
+
+impl BriefingButton {
+    // Left to right
+    const ALL: [BriefingButton; 2] = [BriefingButton::Back, BriefingButton::Play];
+
+    fn text(&self) -> &'static str {
+        match self {
+            BriefingButton::Back => "Back",
+            BriefingButton::Play => "Play",
+        }
+    }
+}
+
+// Picks which special, if any, the level starts with
+#[derive(Component, Clone, Copy, PartialEq)]
+struct BoosterButton(Option<Shape>);
+
+const BUTTON_COLOR: Srgba = Srgba::new(1.0, 1.0, 1.0, 0.1);
+const SELECTED_BOOSTER_COLOR: Srgba = Srgba::new(1.0, 1.0, 1.0, 0.4);
+const PREVIEW_SIZE: f32 = 150.0;
+const ICON_SIZE: f32 = 40.0;
+
+pub fn briefing(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Briefing), setup)
+        .add_systems(
+            FixedUpdate,
+            (
+                press_briefing_buttons.run_if(guide_loaded),
+                go_back_on_cancel,
+                (select_booster, highlight_selected_booster).chain(),
+            )
+                .run_if(in_state(GameState::Briefing)),
+        )
+        .add_systems(OnExit(GameState::Briefing), cleanup);
+}
+
+// The level's settings were picked on the map before coming here
+#[allow(clippy::too_many_arguments)]
+fn setup(
+    mut commands: Commands,
+    current_map: Res<CurrentMap>,
+    needed_matches: Res<NeededMatches>,
+    objective: Res<Objective>,
+    move_limit: Res<MoveLimit>,
+    level_obstacles: Res<LevelObstacles>,
+    map_finishes: Res<MapFinishes>,
+    campaign_map: Res<CampaignMapHandle>,
+    campaign_maps: Res<Assets<CampaignMap>>,
+    best_scores: Res<BestScores>,
+    shape_art: Res<ShapeArt>,
+    mut starting_booster: ResMut<StartingBooster>,
+    mut board_seed: ResMut<BoardSeed>,
+) {
+    starting_booster.0 = None;
+    // The board is spawned from the same seed, so the preview matches it
+    let layout = ObstacleLayout::from_seed(board_seed.pick_next(), &level_obstacles);
+
+    let level = current_map.level().unwrap_or_default();
+    let specials = campaign_maps
+        .get(&campaign_map.0)
+        .map(|campaign_map| campaign_map.unlocked_specials(&map_finishes))
+        .unwrap_or_default();
+    let best_score = match best_scores.get(level) {
+        Some(best_score) => format!("Best score: {best_score}"),
+        None => "Not won yet".to_string(),
+    };
+    let obstacles = [
+        (Shape::Ice, level_obstacles.ice),
+        (Shape::Stone, level_obstacles.stone),
+        (Shape::Chocolate, level_obstacles.chocolate),
+        (Shape::Ingredient, level_obstacles.ingredients),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count > 0)
+    .collect::<Vec<_>>();
+
+    commands
+        .spawn((
+            Briefing,
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.),
+                margin: UiRect::all(Val::Auto),
+                ..default()
+            },
+            Name::new("Briefing Root"),
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new(format!("Level {level}")),
+                TextFont {
+                    font_size: 60.0,
+                    ..default()
+                },
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+            ));
+
+            root.spawn(Text::new(objective.text(needed_matches.0)));
+            root.spawn(Text::new(move_limit.text()));
+
+            // Where the obstacles start, the shapes around them are left out
+            root.spawn((
+                Node {
+                    width: Val::Px(PREVIEW_SIZE),
+                    height: Val::Px(PREVIEW_SIZE),
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::fr(BOARD_SIZE as u16, 1.0),
+                    grid_template_rows: RepeatedGridTrack::fr(BOARD_SIZE as u16, 1.0),
+                    padding: UiRect::all(Val::Px(3.)),
+                    row_gap: Val::Px(3.),
+                    column_gap: Val::Px(3.),
+                    margin: UiRect::vertical(Val::Px(10.)),
+                    ..default()
+                },
+                BackgroundColor(Srgba::new(1.0, 1.0, 1.0, 0.05).into()),
+                Name::new("Board Preview"),
+            ))
+            .with_children(|preview| {
+                for i in 0..BOARD_SIZE * BOARD_SIZE {
+                    let chained = layout.chained(i);
+                    let mut cell = preview.spawn((
+                        Node {
+                            border: UiRect::all(Val::Px(if chained { 2. } else { 0. })),
+                            ..default()
+                        },
+                        BackgroundColor(BUTTON_COLOR.into()),
+                        BorderColor(GRAY_400.into()),
+                    ));
+
+                    if let Some(obstacle) = layout.obstacle(i) {
+                        cell.with_child((
+                            Node {
+                                width: Val::Percent(100.),
+                                height: Val::Percent(100.),
+                                ..default()
+                            },
+                            obstacle.image(&shape_art),
+                        ));
+                    }
+                }
+            });
+
+            if let Some(obstacles_text) = level_obstacles.text() {
+                root.spawn(Text::new(obstacles_text));
+            }
+            if !obstacles.is_empty() {
+                root.spawn((
+                    Node {
+                        column_gap: Val::Px(15.),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    Name::new("Obstacle Icons"),
+                ))
+                .with_children(|icons| {
+                    for (obstacle, count) in obstacles {
+                        icons.spawn(icon(obstacle, &shape_art));
+                        icons.spawn(Text::new(format!("x{count}")));
+                    }
+                });
+            }
+
+            root.spawn(Text::new(if specials.is_empty() {
+                "Specials unlocked: none yet"
+            } else {
+                "Specials unlocked:"
+            }));
+            root.spawn((
+                Node {
+                    column_gap: Val::Px(10.),
+                    ..default()
+                },
+                Name::new("Unlocked Specials"),
+            ))
+            .with_children(|icons| {
+                for special in &specials {
+                    icons.spawn(icon(*special, &shape_art));
+                }
+            });
+
+            root.spawn(Text::new(best_score));
+
+            // Any unlocked special can be started with, on a free spot of the board
+            if !specials.is_empty() {
+                root.spawn((
+                    Text::new("Start with a booster:"),
+                    Node {
+                        margin: UiRect::top(Val::Px(20.)),
+                        ..default()
+                    },
+                ));
+                root.spawn((
+                    Node {
+                        column_gap: Val::Px(10.),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    Name::new("Booster Buttons"),
+                ))
+                .with_children(|boosters| {
+                    let booster_buttons =
+                        std::iter::once(None).chain(specials.iter().copied().map(Some));
+                    for (i, booster) in booster_buttons.enumerate() {
+                        let mut button = boosters.spawn((
+                            BoosterButton(booster),
+                            Button,
+                            Node {
+                                padding: UiRect::all(Val::Px(5.)),
+                                ..default()
+                            },
+                            BackgroundColor(
+                                if booster.is_none() {
+                                    SELECTED_BOOSTER_COLOR
+                                } else {
+                                    BUTTON_COLOR
+                                }
+                                .into(),
+                            ),
+                            Focusable((BriefingButton::ALL.len() + i) as u32),
+                        ));
+
+                        match booster {
+                            Some(booster) => button.with_child(icon(booster, &shape_art)),
+                            None => button.with_child(Text::new("None")),
+                        };
+                    }
+                });
+            }
+
+            root.spawn((
+                Node {
+                    column_gap: Val::Px(20.),
+                    margin: UiRect::top(Val::Px(30.)),
+                    ..default()
+                },
+                Name::new("Briefing Buttons"),
+            ))
+            .with_children(|buttons| {
+                // Numbered from the bottom row up so that up moves focus up, like on the map
+                for (i, briefing_button) in BriefingButton::ALL.iter().enumerate() {
+                    let mut button = buttons.spawn((
+                        *briefing_button,
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(20.), Val::Px(10.)),
+                            ..default()
+                        },
+                        BackgroundColor(BUTTON_COLOR.into()),
+                        Focusable(i as u32),
+                    ));
+                    button.with_child(Text::new(briefing_button.text()));
+
+                    if *briefing_button == BriefingButton::Play {
+                        button.insert(Focused);
+                    }
+                }
+            });
+        });
+}
+
+fn icon(shape: Shape, shape_art: &ShapeArt) -> (Node, ImageNode) {
+    (
+        Node {
+            width: Val::Px(ICON_SIZE),
+            height: Val::Px(ICON_SIZE),
+            ..default()
+        },
+        shape.image(shape_art),
+    )
+}
+
+fn press_briefing_buttons(
+    interactions: Query<(&Interaction, &BriefingButton), Changed<Interaction>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, briefing_button) in interactions.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match briefing_button {
+            BriefingButton::Back => game_state.set(GameState::Map),
+            BriefingButton::Play => game_state.set(GameState::Board),
+        }
+    }
+}
+
+fn go_back_on_cancel(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if cancel_just_pressed(&keys, &gamepads) {
+        game_state.set(GameState::Map);
+    }
+}
+
+fn select_booster(
+    interactions: Query<(&Interaction, &BoosterButton), Changed<Interaction>>,
+    mut starting_booster: ResMut<StartingBooster>,
+) {
+    for (interaction, booster_button) in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            starting_booster.0 = booster_button.0;
+        }
+    }
+}
+
+fn highlight_selected_booster(
+    starting_booster: Res<StartingBooster>,
+    mut booster_buttons: Query<(&BoosterButton, &mut BackgroundColor)>,
+) {
+    if !starting_booster.is_changed() {
+        return;
+    }
+
+    for (booster_button, mut background) in booster_buttons.iter_mut() {
+        background.0 = if booster_button.0 == starting_booster.0 {
+            SELECTED_BOOSTER_COLOR
+        } else {
+            BUTTON_COLOR
+        }
+        .into();
+    }
+}
+
+fn cleanup(mut commands: Commands, briefing: Query<Entity, With<Briefing>>) {
+    for briefing in briefing.iter() {
+        commands.entity(briefing).despawn_recursive();
+    }
+}
@@ -1,32 +1,119 @@
-use std::iter::repeat_with;
-
 use bevy::prelude::*;
 
 use crate::{
-    board::{get_board_styling, get_shape_styling, shape::Shape, utils::get_index, BOARD_SIZE},
-    GameState, MapFinishes,
+    board::{
+        board_idle, get_board_styling, get_shape_styling,
+        shape::{Shape, ShapeArt},
+        spawn_shape,
+        utils::{get_entity, get_row_col},
+        AllowedSwaps, Board, BoardRng, BoardState, MoveMade, SwapShapes, BOARD_SIZE, TUTORIAL_SEED,
+    },
+    focus::{Focusable, Focused},
+    tutorial::{DemoStep, TutorialBoard, Tutorials, TutorialsHandle, Unlock},
+    CurrentMap, GameState,
 };
 
 #[derive(Component)]
 struct ExplanationScreen;
 
+// Says whose turn it is during a demo
+#[derive(Component)]
+struct Prompt;
+
+#[derive(Component)]
+struct ContinueButton;
+
+// The demo on the live board is watched first, then the player makes the same moves
+#[derive(Resource)]
+struct Demo {
+    board: [Shape; BOARD_SIZE * BOARD_SIZE],
+    steps: Vec<DemoStep>,
+    next_step: usize,
+    phase: DemoPhase,
+    // Counts while the board is idle so every move can be seen settling
+    wait: Timer,
+}
+
+#[derive(PartialEq)]
+enum DemoPhase {
+    Watching,
+    Trying,
+    Done,
+}
+
+const DEMO_STEP_DELAY: f32 = 1.0;
+
 pub fn explanation_screen(app: &mut App) {
     app.add_systems(OnEnter(GameState::ExplanationScreen), setup)
         .add_systems(
             FixedUpdate,
-            go_to_map_on_click.run_if(in_state(GameState::ExplanationScreen)),
+            (
+                go_to_map_on_click,
+                (follow_player_moves, play_demo.run_if(board_idle))
+                    .chain()
+                    .run_if(resource_exists::<Demo>),
+            )
+                .run_if(in_state(GameState::ExplanationScreen)),
         )
         .add_systems(OnExit(GameState::ExplanationScreen), cleanup);
 }
 
-fn get_board_shapes(shape: Shape) -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-    repeat_with(|| ShapeOrInvisible::Shape(shape))
-        .take(BOARD_SIZE * BOARD_SIZE)
-        .collect::<Vec<ShapeOrInvisible>>()
-        .try_into()
-        .unwrap()
-}
-fn setup(mut commands: Commands, map_finishes: Res<MapFinishes>) {
+fn setup(
+    mut commands: Commands,
+    current_map: Res<CurrentMap>,
+    shape_art: Res<ShapeArt>,
+    tutorials: Res<TutorialsHandle>,
+    tutorial_assets: Res<Assets<Tutorials>>,
+    mut allowed_swaps: ResMut<AllowedSwaps>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let page = Unlock::from_current_map(&current_map).and_then(|unlock| {
+        tutorial_assets
+            .get(&tutorials.0)
+            .and_then(|tutorials| tutorials.page(unlock))
+    });
+
+    let Some(page) = page else {
+        game_state.set(GameState::Map);
+        return;
+    };
+
+    let explanation_boards = match &page.demo {
+        Some(demo) => {
+            let board = commands
+                .spawn((Board, get_board_styling(), Name::new("Tutorial Board")))
+                .id();
+            spawn_demo_shapes(&mut commands, board, &demo.board, &shape_art);
+
+            // The demo makes its own moves until it's the player's turn
+            *allowed_swaps = AllowedSwaps::None;
+            commands.insert_resource(BoardRng::seeded(TUTORIAL_SEED));
+            commands.insert_resource(Demo {
+                board: demo.board,
+                steps: demo.steps.clone(),
+                next_step: 0,
+                phase: DemoPhase::Watching,
+                wait: Timer::from_seconds(DEMO_STEP_DELAY, TimerMode::Once),
+            });
+
+            vec![board]
+        }
+        None => page
+            .boards
+            .iter()
+            .enumerate()
+            .map(|(i, shapes)| {
+                spawn_board(
+                    &mut commands,
+                    &shape_art,
+                    shapes,
+                    Name::new(format!("Explanation Board {}", i + 1)),
+                )
+            })
+            .collect::<Vec<Entity>>(),
+    };
+    let has_demo = page.demo.is_some();
+
     let explanation_board_container = commands
         .spawn((
             Node {
@@ -35,61 +122,168 @@ fn setup(mut commands: Commands, map_finishes: Res<MapFinishes>) {
             },
             Name::new("Explanation Board Container"),
         ))
+        .add_children(&explanation_boards)
         .id();
 
-    let mut explanation_boards: Option<[Entity; 3]> = None;
-    let mut explanation_text: Option<String> = None;
-    if map_finishes.map3 {
-        explanation_boards = Some(spawn_eliminator_explanation_boards(&mut commands));
-        explanation_text = Some("Eliminator unlocked, destroys randomly".to_string());
-    } else if map_finishes.map2 {
-        explanation_boards = Some(spawn_liner_explanation_boards(&mut commands));
-        explanation_text = Some("Liner unlocked, destroys in a line,".to_string());
-    } else if map_finishes.map1 {
-        explanation_boards = Some(spawn_bomb_explanation_boards(&mut commands));
-        explanation_text = Some("Bomb unlocked, destroys in a 3x3".to_string());
-    }
-
-    let explanation_boards = explanation_boards.unwrap();
-
     commands
-        .entity(explanation_board_container)
-        .add_children(&explanation_boards);
-
-    let text = commands
         .spawn((
-            Text::new(explanation_text.unwrap()),
-            Name::new("Explanation Text"),
+            ExplanationScreen,
             Node {
-                margin: UiRect::top(Val::Auto),
+                width: Val::Vw(100.),
+                height: Val::Vh(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(20.),
                 ..default()
             },
+            Name::new("ExplanationScreen Root"),
         ))
-        .id();
+        .with_children(|root| {
+            root.spawn((
+                Text::new(page.caption.clone()),
+                Name::new("Explanation Text"),
+            ));
+
+            if has_demo {
+                root.spawn((Prompt, Text::new("Watch closely"), Name::new("Prompt Text")));
+            }
+        })
+        .add_child(explanation_board_container)
+        .with_children(|root| {
+            root.spawn((
+                ContinueButton,
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(10.)),
+                    ..default()
+                },
+                BackgroundColor(Srgba::new(1.0, 1.0, 1.0, 0.1).into()),
+                Focusable(0),
+                Focused,
+            ))
+            .with_child(Text::new(if has_demo { "Skip" } else { "Continue" }));
+        });
+}
 
-    let mut root = commands.spawn((
-        ExplanationScreen,
-        Node {
-            display: Display::Grid,
-            width: Val::Vw(100.),
-            height: Val::Vh(100.),
-            grid_template_rows: vec![GridTrack::fr(0.3), GridTrack::fr(1.0)],
-            grid_auto_flow: GridAutoFlow::Column,
-            justify_items: JustifyItems::Center,
-            align_items: AlignItems::Center,
-            ..default()
+fn spawn_demo_shapes(
+    commands: &mut Commands,
+    board: Entity,
+    shapes: &[Shape; BOARD_SIZE * BOARD_SIZE],
+    shape_art: &ShapeArt,
+) {
+    let shapes = shapes
+        .iter()
+        .map(|shape| spawn_shape(commands, *shape, shape_art))
+        .collect::<Vec<_>>();
+
+    commands.entity(board).add_children(&shapes);
+}
+
+// Every move starts the wait for the next step over, and the player only gets to make the move
+// they're on once the board has settled
+fn follow_player_moves(
+    mut move_made: EventReader<MoveMade>,
+    mut demo: ResMut<Demo>,
+    mut allowed_swaps: ResMut<AllowedSwaps>,
+) {
+    let moves = move_made.read().count();
+    if moves == 0 {
+        return;
+    }
+
+    demo.wait.reset();
+    if demo.phase == DemoPhase::Trying {
+        demo.next_step += moves;
+        *allowed_swaps = AllowedSwaps::None;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_demo(
+    time: Res<Time>,
+    board: Query<(Entity, &Children), With<Board>>,
+    shapes: Query<&Shape>,
+    shape_art: Res<ShapeArt>,
+    mut demo: ResMut<Demo>,
+    mut allowed_swaps: ResMut<AllowedSwaps>,
+    mut prompt: Query<&mut Text, With<Prompt>>,
+    continue_button: Query<&Children, With<ContinueButton>>,
+    mut continue_text: Query<&mut Text, Without<Prompt>>,
+    mut swap_shapes: EventWriter<SwapShapes>,
+    mut commands: Commands,
+) {
+    demo.wait.tick(time.delta());
+    let (board, board_children) = board.single();
+    let step = demo.steps.get(demo.next_step).copied();
+
+    match demo.phase {
+        DemoPhase::Watching if demo.wait.finished() => {
+            demo.wait.reset();
+
+            if let Some(step) = step {
+                if let Some([shape1, shape2]) = shapes_to_swap(step, board_children, &shapes) {
+                    swap_shapes.send(SwapShapes(shape1, shape2));
+                }
+                demo.next_step += 1;
+                return;
+            }
+
+            // Back to how it started for the player to have a go
+            commands.entity(board).despawn_descendants();
+            spawn_demo_shapes(&mut commands, board, &demo.board, &shape_art);
+            // Refilled the same way as in the demo, so the same moves work
+            commands.insert_resource(BoardRng::seeded(TUTORIAL_SEED));
+            demo.next_step = 0;
+            demo.phase = DemoPhase::Trying;
+            prompt.single_mut().0 = "Your turn, make the same moves".to_string();
+        }
+        DemoPhase::Trying => match step {
+            Some(step) => *allowed_swaps = step.allowed_swaps(),
+            None if demo.wait.finished() => {
+                demo.phase = DemoPhase::Done;
+                prompt.single_mut().0 = "Well done!".to_string();
+                for text in continue_button.iter().flatten() {
+                    if let Ok(mut text) = continue_text.get_mut(*text) {
+                        text.0 = "Continue".to_string();
+                    }
+                }
+            }
+            None => {}
         },
-        Name::new("ExplanationScreen Root"),
-        Button,
-    ));
+        _ => {}
+    }
+}
 
-    root.add_child(text);
-    root.add_child(explanation_board_container);
+// Specials can end up anywhere after falling, so they're looked for rather than placed
+fn shapes_to_swap(step: DemoStep, board: &Children, shapes: &Query<&Shape>) -> Option<[Entity; 2]> {
+    match step {
+        DemoStep::Swap((row1, col1), (row2, col2)) => Some([
+            *get_entity(row1, col1, board)?,
+            *get_entity(row2, col2, board)?,
+        ]),
+        DemoStep::Detonate => {
+            let special = *board
+                .iter()
+                .find(|e| shapes.get(**e).is_ok_and(|s| s.is_special()))?;
+            let (row, col) = get_row_col(&special, board);
+            let (row, col) = (row as i32, col as i32);
+
+            let neighbor = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .filter_map(|(row_offset, col_offset)| {
+                    get_entity(row + row_offset, col + col_offset, board)
+                })
+                .find(|e| shapes.get(**e).is_ok_and(|s| !s.is_blocker()))?;
+
+            Some([special, *neighbor])
+        }
+    }
 }
 
 fn go_to_map_on_click(
     mut game_state: ResMut<NextState<GameState>>,
-    interactions: Query<&Interaction, Changed<Interaction>>,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>,
 ) {
     for interaction in &interactions {
         if *interaction == Interaction::Pressed {
@@ -98,177 +292,38 @@ fn go_to_map_on_click(
     }
 }
 
-#[derive(Debug)]
-enum ShapeOrInvisible {
-    Shape(Shape),
-    Invisible,
-}
-
 fn spawn_board(
     commands: &mut Commands,
-    shapes: [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE],
+    shape_art: &ShapeArt,
+    shapes: &TutorialBoard,
     name: Name,
 ) -> Entity {
-    let board = commands
+    commands
         .spawn((get_board_styling(), name))
         .with_children(|parent| {
-            for shape_or_invisible in shapes {
-                if let ShapeOrInvisible::Shape(shape) = shape_or_invisible {
-                    parent.spawn(get_shape_styling(shape));
+            for shape in shapes {
+                if let Some(shape) = shape {
+                    parent.spawn(get_shape_styling(*shape, shape_art));
                 } else {
-                    parent
-                        .spawn(get_shape_styling(Shape::Red))
-                        .insert(BackgroundColor(Color::NONE));
+                    parent.spawn((get_shape_styling(Shape::Red, shape_art), Visibility::Hidden));
                 }
             }
         })
-        .id();
-
-    board
-}
-
-fn spawn_liner_explanation_boards(commands: &mut Commands) -> [Entity; 3] {
-    return [
-        spawn_board(commands, get_liner_example1(), Name::new("Liner Example 1")),
-        spawn_board(commands, get_liner_example2(), Name::new("Liner Example 2")),
-        spawn_board(commands, get_liner_example3(), Name::new("Liner Example 3")),
-    ];
-
-    fn get_liner_example1() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(1, 3).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(2, 3).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(3, 3).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(4, 3).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(5, 3).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-
-        board_shapes
-    }
-
-    fn get_liner_example2() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(3, 3).unwrap() as usize] =
-            ShapeOrInvisible::Shape(Shape::VerticalLiner);
-
-        board_shapes
-    }
-
-    fn get_liner_example3() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(1, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(2, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(3, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(4, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(5, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-
-        board_shapes
-    }
-}
-
-fn spawn_bomb_explanation_boards(commands: &mut Commands) -> [Entity; 3] {
-    return [
-        spawn_board(commands, get_bomb_example1(), Name::new("Bomb Example 1")),
-        spawn_board(commands, get_bomb_example2(), Name::new("Bomb Example 2")),
-        spawn_board(commands, get_bomb_example3(), Name::new("Bomb Example 3")),
-    ];
-
-    fn get_bomb_example1() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(2, 2).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(2, 3).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(2, 4).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(3, 4).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(4, 4).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-
-        board_shapes
-    }
-
-    fn get_bomb_example2() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(2, 4).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Bomb);
-
-        board_shapes
-    }
-
-    fn get_bomb_example3() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(1, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(1, 4).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(1, 5).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(2, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(2, 4).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(2, 5).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(3, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(3, 4).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(3, 5).unwrap() as usize] = ShapeOrInvisible::Invisible;
-
-        board_shapes
-    }
+        .id()
 }
 
-fn spawn_eliminator_explanation_boards(commands: &mut Commands) -> [Entity; 3] {
-    return [
-        spawn_board(
-            commands,
-            get_eliminator_example1(),
-            Name::new("Eliminator Board 1"),
-        ),
-        spawn_board(
-            commands,
-            get_eliminator_example2(),
-            Name::new("Eliminator Board 2"),
-        ),
-        spawn_board(
-            commands,
-            get_eliminator_example3(),
-            Name::new("Eliminator Board 3"),
-        ),
-    ];
-
-    fn get_eliminator_example1() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(3, 1).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(3, 2).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(3, 3).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(3, 4).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-        board_shapes[get_index(3, 5).unwrap() as usize] = ShapeOrInvisible::Shape(Shape::Red);
-
-        board_shapes
-    }
-
-    fn get_eliminator_example2() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-        board_shapes[get_index(3, 3).unwrap() as usize] =
-            ShapeOrInvisible::Shape(Shape::Eliminator);
-
-        board_shapes
-    }
-
-    fn get_eliminator_example3() -> [ShapeOrInvisible; BOARD_SIZE * BOARD_SIZE] {
-        let mut board_shapes = get_board_shapes(Shape::Blue);
-
-        board_shapes[get_index(3, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(3, 2).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(1, 5).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(1, 1).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(4, 4).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(2, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(5, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(2, 1).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(4, 1).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(1, 3).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(4, 2).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(5, 2).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(1, 2).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(2, 5).unwrap() as usize] = ShapeOrInvisible::Invisible;
-        board_shapes[get_index(4, 5).unwrap() as usize] = ShapeOrInvisible::Invisible;
-
-        board_shapes
+// Nothing is spawned when there was nothing to explain. Skipping can leave the demo mid-move.
+fn cleanup(
+    mut commands: Commands,
+    explanation_screen: Query<Entity, With<ExplanationScreen>>,
+    mut allowed_swaps: ResMut<AllowedSwaps>,
+    mut board_state: ResMut<NextState<BoardState>>,
+) {
+    for explanation_screen in explanation_screen.iter() {
+        commands.entity(explanation_screen).despawn_recursive();
     }
-}
 
-fn cleanup(mut commands: Commands, explanation_screen: Query<Entity, With<ExplanationScreen>>) {
-    commands
-        .entity(explanation_screen.single())
-        .despawn_recursive();
+    commands.remove_resource::<Demo>();
+    *allowed_swaps = AllowedSwaps::Any;
+    board_state.set(BoardState::InPlay);
 }
@@ -0,0 +1,151 @@
+use bevy::{color::palettes::tailwind::GRAY_50, prelude::*, ui::UiSystem};
+
+// Buttons that can be moved between with the keyboard or a gamepad, in the order given
+#[derive(Component)]
+pub struct Focusable(pub u32);
+
+#[derive(Component)]
+pub struct Focused;
+
+// The button pressed with the keyboard or a gamepad. It stays pressed until FixedUpdate, where
+// the click handlers run, has seen it.
+#[derive(Resource, Default)]
+struct KeyboardPress(Option<Entity>);
+
+pub fn focus(app: &mut App) {
+    app.init_resource::<KeyboardPress>()
+        .add_systems(PreUpdate, press_focused.after(UiSystem::Focus))
+        .add_systems(FixedLast, release_keyboard_press)
+        .add_systems(Update, (move_menu_focus, show_focus));
+}
+
+// Up is negative y, like rows on the board
+pub fn direction_just_pressed(
+    keys: &ButtonInput<KeyCode>,
+    gamepads: &Query<&Gamepad>,
+) -> Option<IVec2> {
+    let just_pressed = |arrow: KeyCode, wasd: KeyCode, d_pad: GamepadButton| {
+        keys.just_pressed(arrow)
+            || keys.just_pressed(wasd)
+            || gamepads.iter().any(|g| g.just_pressed(d_pad))
+    };
+
+    if just_pressed(KeyCode::ArrowUp, KeyCode::KeyW, GamepadButton::DPadUp) {
+        Some(IVec2::NEG_Y)
+    } else if just_pressed(KeyCode::ArrowDown, KeyCode::KeyS, GamepadButton::DPadDown) {
+        Some(IVec2::Y)
+    } else if just_pressed(KeyCode::ArrowLeft, KeyCode::KeyA, GamepadButton::DPadLeft) {
+        Some(IVec2::NEG_X)
+    } else if just_pressed(KeyCode::ArrowRight, KeyCode::KeyD, GamepadButton::DPadRight) {
+        Some(IVec2::X)
+    } else {
+        None
+    }
+}
+
+pub fn confirm_just_pressed(keys: &ButtonInput<KeyCode>, gamepads: &Query<&Gamepad>) -> bool {
+    keys.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+        || gamepads
+            .iter()
+            .any(|g| g.just_pressed(GamepadButton::South))
+}
+
+pub fn cancel_just_pressed(keys: &ButtonInput<KeyCode>, gamepads: &Query<&Gamepad>) -> bool {
+    keys.just_pressed(KeyCode::Escape)
+        || gamepads.iter().any(|g| g.just_pressed(GamepadButton::East))
+}
+
+// Up and right go to the next button, down and left to the previous one
+fn move_menu_focus(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    focusables: Query<(Entity, &Focusable)>,
+    focused: Query<Entity, (With<Focused>, With<Focusable>)>,
+    mut commands: Commands,
+) {
+    let Some(direction) = direction_just_pressed(&keys, &gamepads) else {
+        return;
+    };
+
+    let mut focusables = focusables.iter().collect::<Vec<_>>();
+    if focusables.is_empty() {
+        return;
+    }
+    focusables.sort_by_key(|(_, focusable)| focusable.0);
+
+    let next_index = match focused.get_single() {
+        Ok(focused) => {
+            commands.entity(focused).remove::<Focused>();
+
+            let index = focusables.iter().position(|(e, _)| *e == focused).unwrap();
+            if direction == IVec2::NEG_Y || direction == IVec2::X {
+                (index + 1).min(focusables.len() - 1)
+            } else {
+                index.saturating_sub(1)
+            }
+        }
+        Err(_) => 0,
+    };
+
+    commands.entity(focusables[next_index].0).insert(Focused);
+}
+
+fn press_focused(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    focused: Query<Entity, With<Focused>>,
+    mut interactions: Query<&mut Interaction>,
+    mut keyboard_press: ResMut<KeyboardPress>,
+) {
+    if confirm_just_pressed(&keys, &gamepads) {
+        if let Ok(focused) = focused.get_single() {
+            if let Ok(mut interaction) = interactions.get_mut(focused) {
+                *interaction = Interaction::Pressed;
+                keyboard_press.0 = Some(focused);
+            }
+        }
+    }
+
+    // The cursor being away from the button makes `ui_focus_system` reset it, which shouldn't
+    // count as a second press
+    if let Some(pressed) = keyboard_press.0 {
+        if let Ok(mut interaction) = interactions.get_mut(pressed) {
+            if *interaction != Interaction::Pressed {
+                *interaction.bypass_change_detection() = Interaction::Pressed;
+            }
+        }
+    }
+}
+
+fn release_keyboard_press(
+    mut interactions: Query<&mut Interaction>,
+    mut keyboard_press: ResMut<KeyboardPress>,
+) {
+    if let Some(pressed) = keyboard_press.0.take() {
+        if let Ok(mut interaction) = interactions.get_mut(pressed) {
+            *interaction = Interaction::None;
+        }
+    }
+}
+
+fn show_focus(
+    newly_focused: Query<Entity, Added<Focused>>,
+    mut unfocused: RemovedComponents<Focused>,
+    mut commands: Commands,
+) {
+    for entity in unfocused.read() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<BoxShadow>();
+        }
+    }
+
+    for entity in newly_focused.iter() {
+        commands.entity(entity).insert(BoxShadow {
+            color: GRAY_50.into(),
+            x_offset: Val::ZERO,
+            y_offset: Val::ZERO,
+            spread_radius: Val::Px(4.),
+            blur_radius: Val::Px(8.),
+        });
+    }
+}
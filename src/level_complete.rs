@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+
+use crate::{
+    map::{CampaignMap, CampaignMapHandle},
+    results_screen::{format_time, spawn_results_screen, ResultsButton},
+    tutorial::{Tutorials, TutorialsHandle, Unlock},
+    CurrentMap, FirstClear, GameState, LevelStats,
+};
+
+#[derive(Component)]
+struct LevelComplete;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum LevelCompleteButton {
+    Continue,
+    Replay,
+}
+
+impl ResultsButton for LevelCompleteButton {
+    const ALL: &'static [LevelCompleteButton] =
+        &[LevelCompleteButton::Continue, LevelCompleteButton::Replay];
+    const FOCUSED: LevelCompleteButton = LevelCompleteButton::Continue;
+
+    fn text(&self) -> &'static str {
+        match self {
+            LevelCompleteButton::Continue => "Continue",
+            LevelCompleteButton::Replay => "Replay level",
+        }
+    }
+}
+
+pub fn level_complete(app: &mut App) {
+    app.add_systems(OnEnter(GameState::LevelComplete), setup)
+        .add_systems(
+            FixedUpdate,
+            press_level_complete_buttons.run_if(in_state(GameState::LevelComplete)),
+        )
+        .add_systems(OnExit(GameState::LevelComplete), cleanup);
+}
+
+fn setup(mut commands: Commands, level_stats: Res<LevelStats>) {
+    let stats = [
+        format!("Score: {}", level_stats.score),
+        format!("Moves: {}", level_stats.moves),
+        format!("Best combo: {}", level_stats.best_combo),
+        format!("Specials created: {}", level_stats.specials_created),
+        format!("Time: {}", format_time(level_stats.time_played)),
+    ];
+
+    spawn_results_screen::<LevelCompleteButton>(
+        &mut commands,
+        LevelComplete,
+        "LevelComplete",
+        "Level complete!",
+        &stats,
+    );
+}
+
+// Whatever the level unlocks is explained the first time it is won, and winning the last level
+// for the first time finishes the campaign
+fn next_screen(
+    current_map: &CurrentMap,
+    first_clear: &FirstClear,
+    tutorials: Option<&Tutorials>,
+    campaign_map: Option<&CampaignMap>,
+) -> GameState {
+    let has_tutorial = Unlock::from_current_map(current_map)
+        .zip(tutorials)
+        .is_some_and(|(unlock, tutorials)| tutorials.page(unlock).is_some());
+
+    let last_level = current_map.level().is_some_and(|level| {
+        campaign_map.is_some_and(|campaign_map| campaign_map.last_level() == Some(level))
+    });
+
+    if !first_clear.0 {
+        GameState::Map
+    } else if has_tutorial {
+        GameState::ExplanationScreen
+    } else if last_level {
+        GameState::WinScreen
+    } else {
+        GameState::Map
+    }
+}
+
+// The level settings are still those of the level just won, so replaying goes back to the board
+#[allow(clippy::too_many_arguments)]
+fn press_level_complete_buttons(
+    interactions: Query<(&Interaction, &LevelCompleteButton), Changed<Interaction>>,
+    current_map: Res<CurrentMap>,
+    first_clear: Res<FirstClear>,
+    tutorials: Res<TutorialsHandle>,
+    tutorial_assets: Res<Assets<Tutorials>>,
+    campaign_map: Res<CampaignMapHandle>,
+    campaign_maps: Res<Assets<CampaignMap>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, level_complete_button) in interactions.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match level_complete_button {
+            LevelCompleteButton::Continue => game_state.set(next_screen(
+                &current_map,
+                &first_clear,
+                tutorial_assets.get(&tutorials.0),
+                campaign_maps.get(&campaign_map.0),
+            )),
+            LevelCompleteButton::Replay => game_state.set(GameState::Board),
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, level_complete: Query<Entity, With<LevelComplete>>) {
+    commands.entity(level_complete.single()).despawn_recursive();
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::{
+    results_screen::{format_time, spawn_results_screen, ResultsButton},
+    GameState, LevelStats,
+};
+
+#[derive(Component)]
+struct LevelFailed;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum LevelFailedButton {
+    Retry,
+    Map,
+}
+
+impl ResultsButton for LevelFailedButton {
+    const ALL: &'static [LevelFailedButton] = &[LevelFailedButton::Retry, LevelFailedButton::Map];
+    const FOCUSED: LevelFailedButton = LevelFailedButton::Retry;
+
+    fn text(&self) -> &'static str {
+        match self {
+            LevelFailedButton::Retry => "Try again",
+            LevelFailedButton::Map => "Back to map",
+        }
+    }
+}
+
+pub fn level_failed(app: &mut App) {
+    app.add_systems(OnEnter(GameState::LevelFailed), setup)
+        .add_systems(
+            FixedUpdate,
+            press_level_failed_buttons.run_if(in_state(GameState::LevelFailed)),
+        )
+        .add_systems(OnExit(GameState::LevelFailed), cleanup);
+}
+
+fn setup(mut commands: Commands, level_stats: Res<LevelStats>) {
+    let stats = [
+        format!("Score: {}", level_stats.score),
+        format!("Moves: {}", level_stats.moves),
+        format!("Time: {}", format_time(level_stats.time_played)),
+    ];
+
+    spawn_results_screen::<LevelFailedButton>(
+        &mut commands,
+        LevelFailed,
+        "LevelFailed",
+        "Out of moves",
+        &stats,
+    );
+}
+
+// The level settings are still those of the level just lost, so trying again goes back to the board
+fn press_level_failed_buttons(
+    interactions: Query<(&Interaction, &LevelFailedButton), Changed<Interaction>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, level_failed_button) in interactions.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match level_failed_button {
+            LevelFailedButton::Retry => game_state.set(GameState::Board),
+            LevelFailedButton::Map => game_state.set(GameState::Map),
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, level_failed: Query<Entity, With<LevelFailed>>) {
+    commands.entity(level_failed.single()).despawn_recursive();
+}
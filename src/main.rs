@@ -1,7 +1,11 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::Deserialize;
 
 mod board;
-use board::{board, MatchMade};
+use board::{board, board_idle, shape::Shape, MatchMade};
 
 mod map;
 use map::map;
@@ -15,6 +19,33 @@ use start_screen::start_screen;
 mod explanation_screen;
 use explanation_screen::explanation_screen;
 
+mod focus;
+use focus::focus;
+
+mod settings;
+use settings::settings;
+
+mod audio;
+use audio::audio;
+
+mod pause_menu;
+use pause_menu::pause_menu;
+
+mod level_complete;
+use level_complete::level_complete;
+
+mod tutorial;
+use tutorial::tutorial;
+
+mod briefing;
+use briefing::briefing;
+
+mod level_failed;
+use level_failed::level_failed;
+
+mod results_screen;
+
+#[cfg(debug_assertions)]
 mod dev_hotkeys;
 
 #[derive(Resource)]
@@ -23,27 +54,144 @@ pub struct TotalMatches(u32);
 #[derive(Resource)]
 pub struct NeededMatches(u32);
 
-#[derive(Resource, Debug, Default)]
-pub struct MapFinishes {
-    map1: bool,
-    map2: bool,
-    map3: bool,
-    map4: bool,
+#[derive(Resource)]
+pub struct CollectedIngredients(u32);
+
+// How many moves a level has to be won in, if it's limited
+#[derive(Resource, Default)]
+pub struct MoveLimit(Option<u32>);
+
+impl MoveLimit {
+    pub fn text(&self) -> String {
+        match self.0 {
+            Some(move_limit) => format!("{move_limit} moves"),
+            None => "No move limit".to_string(),
+        }
+    }
 }
 
-#[derive(Resource, Default, PartialEq)]
-pub enum CurrentMap {
+// Progress through the current attempt at a level
+#[derive(Resource, Default)]
+pub struct LevelStats {
+    moves: u32,
+    score: u32,
+    // Clears in a row caused by the last move
+    combo: u32,
+    best_combo: u32,
+    specials_created: u32,
+    time_played: f32,
+}
+
+// Totals over every level won in the campaign
+#[derive(Resource, Default)]
+pub struct CampaignStats {
+    moves: u32,
+    score: u32,
+    best_combo: u32,
+    specials_created: u32,
+    time_played: f32,
+}
+
+impl CampaignStats {
+    fn add(&mut self, level_stats: &LevelStats) {
+        self.moves += level_stats.moves;
+        self.score += level_stats.score;
+        self.best_combo = self.best_combo.max(level_stats.best_combo);
+        self.specials_created += level_stats.specials_created;
+        self.time_played += level_stats.time_played;
+    }
+}
+
+// How many different colors of shapes a level spawns
+#[derive(Resource)]
+pub struct ColorCount(usize);
+
+#[derive(Resource, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct LevelObstacles {
+    ice: usize,
+    stone: usize,
+    chains: usize,
+    chocolate: usize,
+    ingredients: usize,
+}
+
+impl LevelObstacles {
+    pub fn text(&self) -> Option<String> {
+        let counts = [
+            (self.ice, "ice"),
+            (self.stone, "stone"),
+            (self.chains, "chains"),
+            (self.chocolate, "chocolate"),
+            (self.ingredients, "ingredients"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{count} {name}"))
+        .collect::<Vec<_>>();
+
+        (!counts.is_empty()).then(|| format!("Obstacles: {}", counts.join(", ")))
+    }
+}
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug, Deserialize)]
+pub enum Objective {
     #[default]
-    None,
-    One,
-    Two,
-    Three,
-    Four,
+    Matches,
+    ClearIce,
+    ClearStone,
+    CollectIngredients(u32),
+}
+
+impl Objective {
+    pub fn text(&self, needed_matches: u32) -> String {
+        match self {
+            Objective::Matches => format!("Make {needed_matches} matches"),
+            Objective::ClearIce => "Clear all the ice".to_string(),
+            Objective::ClearStone => "Break all the stone".to_string(),
+            Objective::CollectIngredients(needed_ingredients) => {
+                format!("Bring down {needed_ingredients} ingredients")
+            }
+        }
+    }
 }
 
+// Levels won so far, by level number
+#[derive(Resource, Debug, Default)]
+pub struct MapFinishes(HashSet<u32>);
+
+impl MapFinishes {
+    pub fn finished(&self, level: u32) -> bool {
+        self.0.contains(&level)
+    }
+
+    // Whether this is the first time the level has been won
+    pub fn finish(&mut self, level: u32) -> bool {
+        self.0.insert(level)
+    }
+}
+
+// Highest score each level has been won with, by level number
+#[derive(Resource, Default)]
+pub struct BestScores(HashMap<u32, u32>);
+
+impl BestScores {
+    pub fn get(&self, level: u32) -> Option<u32> {
+        self.0.get(&level).copied()
+    }
+}
+
+// Whether the level just won had never been won before
+#[derive(Resource, Default)]
+pub struct FirstClear(bool);
+
+// The level picked on the map, numbered like in `assets/map`
+#[derive(Resource, Default, PartialEq)]
+pub struct CurrentMap(Option<u32>);
+
 impl CurrentMap {
-    pub fn get(&self) -> &Self {
-        self
+    pub fn level(&self) -> Option<u32> {
+        self.0
     }
 }
 
@@ -55,30 +203,83 @@ pub enum GameState {
     #[default]
     StartScreen,
     ExplanationScreen,
+    Settings,
+    LevelComplete,
+    LevelFailed,
+    Briefing,
+}
+
+// Only exists while on the board
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Board)]
+pub enum LevelState {
+    #[default]
+    Playing,
+    Paused,
 }
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(bevy::log::LogPlugin {
-            //level: bevy::log::Level::DEBUG,
-            ..default()
-        }))
-        .init_state::<GameState>()
-        //.add_plugins(WorldInspectorPlugin::new())
-        //.add_plugins(dev_hotkeys)
-        .add_plugins(board)
-        .add_plugins(map)
-        .add_plugins(win_screen)
-        .add_plugins(start_screen)
-        .add_plugins(explanation_screen)
-        .add_systems(Startup, setup_camera)
-        .add_systems(FixedUpdate, increment_total_matches)
-        .add_systems(FixedUpdate, go_to_next_screen)
-        .insert_resource(TotalMatches(0))
-        .insert_resource(NeededMatches(30))
-        .insert_resource(MapFinishes::default())
-        .insert_resource(CurrentMap::None)
-        .run();
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(bevy::log::LogPlugin {
+        //level: bevy::log::Level::DEBUG,
+        ..default()
+    }))
+    .init_state::<GameState>()
+    .add_sub_state::<LevelState>()
+    //.add_plugins(WorldInspectorPlugin::new())
+    .add_plugins(settings)
+    .add_plugins(board)
+    .add_plugins(map)
+    .add_plugins(win_screen)
+    .add_plugins(start_screen)
+    .add_plugins(explanation_screen)
+    .add_plugins(focus)
+    .add_plugins(audio)
+    .add_plugins(pause_menu)
+    .add_plugins(level_complete)
+    .add_plugins(tutorial)
+    .add_plugins(briefing)
+    .add_plugins(level_failed)
+    .add_systems(Startup, setup_camera)
+    // Only the player's matches count, not those made by tutorial demos
+    .add_systems(
+        FixedUpdate,
+        increment_total_matches.run_if(in_state(LevelState::Playing)),
+    )
+    .add_systems(
+        FixedUpdate,
+        go_to_next_screen
+            .run_if(in_state(GameState::Board))
+            .run_if(objective_complete),
+    )
+    // After the last move has settled, in case it won the level
+    .add_systems(
+        FixedUpdate,
+        go_to_level_failed
+            .run_if(in_state(LevelState::Playing))
+            .run_if(board_idle)
+            .run_if(out_of_moves)
+            .run_if(not(objective_complete)),
+    )
+    .insert_resource(TotalMatches(0))
+    .insert_resource(NeededMatches(30))
+    .insert_resource(CollectedIngredients(0))
+    .insert_resource(MoveLimit::default())
+    .insert_resource(LevelStats::default())
+    .insert_resource(CampaignStats::default())
+    .insert_resource(BestScores::default())
+    .insert_resource(FirstClear::default())
+    .insert_resource(ColorCount(4))
+    .insert_resource(LevelObstacles::default())
+    .insert_resource(Objective::default())
+    .insert_resource(MapFinishes::default())
+    .insert_resource(CurrentMap::default());
+
+    // Z makes a match, to get through levels quickly
+    #[cfg(debug_assertions)]
+    app.add_plugins(dev_hotkeys::dev_hotkeys);
+
+    app.run();
 }
 
 fn setup_camera(mut commands: Commands) {
@@ -94,17 +295,58 @@ fn increment_total_matches(
     }
 }
 
-fn go_to_next_screen(
+pub fn objective_complete(
     total_matches: Res<TotalMatches>,
     needed_matches: Res<NeededMatches>,
-    mut state: ResMut<NextState<GameState>>,
-    current_map: Res<CurrentMap>,
-) {
-    if total_matches.0 >= needed_matches.0 {
-        if *current_map == CurrentMap::Four {
-            state.set(GameState::WinScreen);
-        } else {
-            state.set(GameState::ExplanationScreen);
+    collected_ingredients: Res<CollectedIngredients>,
+    objective: Res<Objective>,
+    shapes: Query<&Shape>,
+) -> bool {
+    match *objective {
+        Objective::Matches => total_matches.0 >= needed_matches.0,
+        Objective::ClearIce => !shapes.iter().any(|s| *s == Shape::Ice),
+        Objective::ClearStone => !shapes.iter().any(|s| *s == Shape::Stone),
+        Objective::CollectIngredients(needed_ingredients) => {
+            collected_ingredients.0 >= needed_ingredients
         }
     }
 }
+
+fn go_to_next_screen(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::LevelComplete);
+}
+
+fn out_of_moves(move_limit: Res<MoveLimit>, level_stats: Res<LevelStats>) -> bool {
+    move_limit
+        .0
+        .is_some_and(|move_limit| level_stats.moves >= move_limit)
+}
+
+fn go_to_level_failed(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::LevelFailed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_obstacles_have_no_text() {
+        assert_eq!(LevelObstacles::default().text(), None);
+    }
+
+    #[test]
+    fn obstacles_text_lists_only_those_on_the_board() {
+        let level_obstacles = LevelObstacles {
+            stone: 1,
+            chains: 3,
+            chocolate: 2,
+            ..default()
+        };
+
+        assert_eq!(
+            level_obstacles.text().as_deref(),
+            Some("Obstacles: 1 stone, 3 chains, 2 chocolate")
+        );
+    }
+}
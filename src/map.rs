@@ -1,64 +1,203 @@
-use std::slice::Iter;
-
 use bevy::{
-    color::palettes::tailwind::{GRAY_50, GRAY_950, GREEN_300},
+    asset::{io::Reader, AssetLoader, LoadContext},
+    color::palettes::tailwind::{GRAY_50, GRAY_600},
+    input::mouse::{MouseScrollUnit, MouseWheel},
     prelude::*,
+    window::PrimaryWindow,
 };
+use serde::Deserialize;
 
-use crate::{CurrentMap, GameState, MapFinishes, NeededMatches};
+use crate::{
+    board::shape::Shape,
+    focus::{Focusable, Focused},
+    BestScores, ColorCount, CurrentMap, GameState, LevelObstacles, MapFinishes, MoveLimit,
+    NeededMatches, Objective,
+};
 
 #[derive(Component)]
 pub struct Map;
 
+// The level number of the node
 #[derive(Component, Clone, Copy, Debug)]
-enum BoardButton {
-    First,
-    Second,
-    Third,
-    Fourth,
-}
-
-impl BoardButton {
-    fn as_str(&self) -> &str {
-        match self {
-            BoardButton::First => "1",
-            BoardButton::Second => "2",
-            BoardButton::Third => "3",
-            BoardButton::Fourth => "4",
+struct BoardButton(u32);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum LevelStatus {
+    Locked,
+    Available,
+    Completed,
+}
+
+// The campaign's levels and where they are on the map, read from `assets/map`
+#[derive(Asset, TypePath, Deserialize)]
+pub struct CampaignMap {
+    // In pixels
+    size: (f32, f32),
+    // In the order they're played, which the path between them follows
+    levels: Vec<MapNode>,
+}
+
+impl CampaignMap {
+    fn node(&self, level: u32) -> Option<(usize, &MapNode)> {
+        self.levels
+            .iter()
+            .enumerate()
+            .find(|(_, node)| node.level == level)
+    }
+
+    // Levels are won in order, each one opening up the next
+    fn status(&self, index: usize, map_finishes: &MapFinishes) -> LevelStatus {
+        if map_finishes.finished(self.levels[index].level) {
+            LevelStatus::Completed
+        } else if self.levels[..index]
+            .iter()
+            .all(|node| map_finishes.finished(node.level))
+        {
+            LevelStatus::Available
+        } else {
+            LevelStatus::Locked
         }
     }
 
-    fn map_available(&self, map_finishes: &MapFinishes) -> bool {
-        match self {
-            BoardButton::First => {
-                !map_finishes.map1 && !map_finishes.map2 && !map_finishes.map3 && !map_finishes.map4
-            }
+    // The specials of every level won so far
+    pub fn unlocked_specials(&self, map_finishes: &MapFinishes) -> Vec<Shape> {
+        self.levels
+            .iter()
+            .filter(|node| map_finishes.finished(node.level))
+            .filter_map(|node| node.unlocks)
+            .collect()
+    }
 
-            BoardButton::Second => {
-                map_finishes.map1 && !map_finishes.map2 && !map_finishes.map3 && !map_finishes.map4
-            }
-            BoardButton::Third => {
-                map_finishes.map1 && map_finishes.map2 && !map_finishes.map3 && !map_finishes.map4
-            }
-            BoardButton::Fourth => {
-                map_finishes.map1 && map_finishes.map2 && map_finishes.map3 && !map_finishes.map4
-            }
-        }
+    pub fn last_level(&self) -> Option<u32> {
+        self.levels.last().map(|node| node.level)
     }
+}
 
-    fn iterator() -> Iter<'static, Self> {
-        [
-            BoardButton::First,
-            BoardButton::Second,
-            BoardButton::Third,
-            BoardButton::Fourth,
-        ]
-        .iter()
+#[derive(Deserialize)]
+struct MapNode {
+    level: u32,
+    // Pixels from the map's top-left to the middle of the node
+    position: (f32, f32),
+    // The best score needed for each star
+    star_scores: [u32; 3],
+    // How the board is set up for the level
+    objective: Objective,
+    needed_matches: u32,
+    #[serde(default)]
+    obstacles: LevelObstacles,
+    color_count: usize,
+    #[serde(default)]
+    move_limit: Option<u32>,
+    // Liners come in both directions, the horizontal one stands for them
+    #[serde(default)]
+    unlocks: Option<Shape>,
+}
+
+impl MapNode {
+    // Winning is always worth a star
+    fn stars(&self, best_score: Option<u32>) -> usize {
+        let best_score = best_score.unwrap_or_default();
+        self.star_scores
+            .iter()
+            .filter(|needed| best_score >= **needed)
+            .count()
+            .max(1)
     }
 }
 
+#[derive(Resource)]
+pub struct CampaignMapHandle(pub Handle<CampaignMap>);
+
+// Played when a level that can't be started is clicked, keeping where the node belongs
+#[derive(Component)]
+struct Shake {
+    timer: Timer,
+    left: f32,
+}
+
+#[derive(Component)]
+struct LevelTooltip;
+
+#[derive(Component)]
+struct LevelTooltipText;
+
+// One tile per icon in the atlas, in this order
+#[derive(Clone, Copy)]
+enum MapIcon {
+    Locked,
+    Available,
+    Completed,
+    Star,
+    EmptyStar,
+    Marker,
+}
+
+#[derive(Resource)]
+struct MapIcons {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+impl FromWorld for MapIcons {
+    fn from_world(world: &mut World) -> Self {
+        let image = world.resource::<AssetServer>().load("map/icons.png");
+        let layout =
+            world
+                .resource_mut::<Assets<TextureAtlasLayout>>()
+                .add(TextureAtlasLayout::from_grid(
+                    UVec2::splat(64),
+                    6,
+                    1,
+                    None,
+                    None,
+                ));
+
+        MapIcons { image, layout }
+    }
+}
+
+impl MapIcons {
+    fn image(&self, icon: MapIcon) -> ImageNode {
+        ImageNode::from_atlas_image(
+            self.image.clone(),
+            TextureAtlas {
+                layout: self.layout.clone(),
+                index: icon as usize,
+            },
+        )
+    }
+}
+
+const NODE_SIZE: f32 = 64.0;
+const PATH_DOT_SIZE: f32 = 10.0;
+const PATH_DOT_SPACING: f32 = 24.0;
+const SCROLL_LINE_HEIGHT: f32 = 20.0;
+const SHAKE_TIME: f32 = 0.3;
+const SHAKE_DISTANCE: f32 = 6.0;
+const TOOLTIP_WIDTH: f32 = 240.0;
+const TOOLTIP_GAP: f32 = 12.0;
+
 pub fn map(app: &mut App) {
-    app.add_systems(OnEnter(GameState::Map), setup)
+    app.init_asset::<CampaignMap>()
+        .register_asset_loader(CampaignMapLoader)
+        .init_resource::<MapIcons>()
+        .add_systems(Startup, load_campaign_map)
+        // Waits for the map's layout to have loaded
+        .add_systems(
+            Update,
+            (
+                setup.run_if(not(any_with_component::<Map>)),
+                (
+                    scroll_with_wheel,
+                    scroll_to_focused,
+                    highlight_board_buttons,
+                    shake_board_buttons,
+                    show_level_tooltip,
+                ),
+            )
+                .chain()
+                .run_if(in_state(GameState::Map)),
+        )
         .add_systems(
             FixedUpdate,
             (go_to_board_on_click,).run_if(in_state(GameState::Map)),
@@ -66,46 +205,229 @@ pub fn map(app: &mut App) {
         .add_systems(OnExit(GameState::Map), cleanup);
 }
 
-fn setup(mut commands: Commands, map_finishes: Res<MapFinishes>) {
+fn load_campaign_map(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(CampaignMapHandle(asset_server.load("map/campaign.map.ron")));
+}
+
+fn setup(
+    mut commands: Commands,
+    map_finishes: Res<MapFinishes>,
+    best_scores: Res<BestScores>,
+    map_icons: Res<MapIcons>,
+    campaign_map: Res<CampaignMapHandle>,
+    campaign_maps: Res<Assets<CampaignMap>>,
+) {
+    let Some(campaign_map) = campaign_maps.get(&campaign_map.0) else {
+        return;
+    };
+
+    let levels = campaign_map
+        .levels
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            (
+                BoardButton(node.level),
+                campaign_map.status(i, &map_finishes),
+                node,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // The player is at the level they're up to, or the last one once they're all won
+    let current_level = levels
+        .iter()
+        .position(|(_, status, _)| *status == LevelStatus::Available)
+        .or(levels.len().checked_sub(1));
+
+    let (width, height) = campaign_map.size;
     commands
         .spawn((
             Map,
             Node {
-                display: Display::Flex,
-                flex_direction: FlexDirection::ColumnReverse,
+                width: Val::Vw(100.),
+                height: Val::Vh(100.),
+                flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                margin: UiRect::all(Val::Auto),
+                overflow: Overflow::scroll_y(),
                 ..default()
             },
-            Name::new("BoardButton Container"),
+            Name::new("Map Root"),
         ))
-        .with_children(|parent| {
-            for board_button in BoardButton::iterator() {
-                parent
-                    .spawn(get_board_button_bundle(*board_button))
-                    .with_children(|parent| {
-                        parent.spawn(get_board_button_text_bundle(*board_button, &map_finishes));
+        .with_children(|root| {
+            root.spawn((
+                Node {
+                    width: Val::Px(width),
+                    height: Val::Px(height),
+                    flex_shrink: 0.,
+                    ..default()
+                },
+                BackgroundColor(Srgba::new(1.0, 1.0, 1.0, 0.05).into()),
+                Name::new("Map Canvas"),
+            ))
+            .with_children(|canvas| {
+                for pair in levels.windows(2) {
+                    let [(_, _, from), (_, to_status, to)] = pair else {
+                        continue;
+                    };
+                    spawn_path(canvas, from.position, to.position, *to_status);
+                }
+
+                for (i, (board_button, status, node)) in levels.iter().enumerate() {
+                    let stars = node.stars(best_scores.get(node.level));
+                    let mut button = canvas.spawn(get_board_button_bundle(
+                        *board_button,
+                        *status,
+                        node.position,
+                        &map_icons,
+                    ));
+                    button.insert(Focusable(i as u32)).with_children(|button| {
+                        spawn_level_label(button, node.level, *status, stars, &map_icons);
                     });
-            }
+
+                    if Some(i) == current_level {
+                        button.insert(Focused);
+                    }
+                }
+
+                if let Some((_, _, node)) = current_level.map(|i| levels[i]) {
+                    let (x, y) = node.position;
+                    canvas.spawn((
+                        map_icons.image(MapIcon::Marker),
+                        Node {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(x - NODE_SIZE * 0.3),
+                            top: Val::Px(y - NODE_SIZE * 1.3),
+                            width: Val::Px(NODE_SIZE * 0.6),
+                            height: Val::Px(NODE_SIZE * 0.6),
+                            ..default()
+                        },
+                        Name::new("Current Level Marker"),
+                    ));
+                }
+
+                canvas
+                    .spawn((
+                        LevelTooltip,
+                        Node {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(TOOLTIP_WIDTH),
+                            padding: UiRect::all(Val::Px(10.)),
+                            ..default()
+                        },
+                        BackgroundColor(Srgba::new(0.0, 0.0, 0.0, 0.85).into()),
+                        BorderRadius::all(Val::Px(6.)),
+                        Visibility::Hidden,
+                        Name::new("Level Tooltip"),
+                    ))
+                    .with_child((
+                        LevelTooltipText,
+                        Text::new(""),
+                        TextFont {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                    ));
+            });
         });
 }
 
+// A dotted line, lit up once the level it leads to is open
+fn spawn_path(canvas: &mut ChildBuilder, from: (f32, f32), to: (f32, f32), status: LevelStatus) {
+    let (from, to) = (Vec2::from(from), Vec2::from(to));
+    let color = if status == LevelStatus::Locked {
+        GRAY_600
+    } else {
+        GRAY_50
+    };
+
+    // Not under the nodes themselves
+    let gap = NODE_SIZE / 2. + PATH_DOT_SPACING / 2.;
+    let length = from.distance(to) - 2. * gap;
+    let dots = (length / PATH_DOT_SPACING).floor().max(0.) as u32 + 1;
+    let direction = (to - from).normalize_or_zero();
+
+    for dot in 0..dots {
+        let center = from + direction * (gap + length * dot as f32 / (dots - 1).max(1) as f32);
+        canvas.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(center.x - PATH_DOT_SIZE / 2.),
+                top: Val::Px(center.y - PATH_DOT_SIZE / 2.),
+                width: Val::Px(PATH_DOT_SIZE),
+                height: Val::Px(PATH_DOT_SIZE),
+                ..default()
+            },
+            BackgroundColor(color.into()),
+            BorderRadius::MAX,
+            Name::new("Map Path"),
+        ));
+    }
+}
+
+fn scroll_with_wheel(
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut map: Query<&mut ScrollPosition, With<Map>>,
+) {
+    for event in mouse_wheel.read() {
+        let dy = match event.unit {
+            MouseScrollUnit::Line => event.y * SCROLL_LINE_HEIGHT,
+            MouseScrollUnit::Pixel => event.y,
+        };
+
+        for mut scroll_position in map.iter_mut() {
+            scroll_position.offset_y -= dy;
+        }
+    }
+}
+
+// Keeps the focused level in the middle of the screen, which is also where the map starts.
+// Layout keeps the scroll inside the map.
+fn scroll_to_focused(
+    focused: Query<&Node, (Added<Focused>, With<BoardButton>)>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut map: Query<&mut ScrollPosition, With<Map>>,
+) {
+    let Ok(node) = focused.get_single() else {
+        return;
+    };
+    let Val::Px(top) = node.top else {
+        return;
+    };
+
+    let window_height = window.get_single().map(|w| w.height()).unwrap_or_default();
+    for mut scroll_position in map.iter_mut() {
+        scroll_position.offset_y = (top + NODE_SIZE / 2. - window_height / 2.).max(0.);
+    }
+}
+
 fn cleanup(mut commands: Commands, map: Query<Entity, With<Map>>) {
-    commands.entity(map.single()).despawn_recursive();
+    for map in map.iter() {
+        commands.entity(map).despawn_recursive();
+    }
 }
 
 fn get_board_button_bundle(
     area: BoardButton,
-) -> (Button, BackgroundColor, Node, Name, BoardButton) {
+    status: LevelStatus,
+    (x, y): (f32, f32),
+    map_icons: &MapIcons,
+) -> (Button, ImageNode, Node, Name, BoardButton) {
+    let icon = match status {
+        LevelStatus::Locked => MapIcon::Locked,
+        LevelStatus::Available => MapIcon::Available,
+        LevelStatus::Completed => MapIcon::Completed,
+    };
+
     (
         Button,
-        BackgroundColor(GRAY_50.into()),
+        map_icons.image(icon),
         Node {
-            width: Val::Px(50.),
-            height: Val::Px(50.),
-            margin: UiRect::all(Val::Px(5.)),
-            justify_content: JustifyContent::Center,
+            position_type: PositionType::Absolute,
+            left: Val::Px(x - NODE_SIZE / 2.),
+            top: Val::Px(y - NODE_SIZE / 2.),
+            width: Val::Px(NODE_SIZE),
+            height: Val::Px(NODE_SIZE),
             ..default()
         },
         Name::new("BoardButton"),
@@ -113,58 +435,340 @@ fn get_board_button_bundle(
     )
 }
 
-fn get_board_button_text_bundle(
-    area: BoardButton,
-    map_finishes: &Res<MapFinishes>,
-) -> (Text, TextLayout, TextColor, Node, Name) {
-    let text_color = if area.map_available(map_finishes) {
-        GREEN_300
-    } else {
-        GRAY_950
-    };
+// The level's number under its node, with the stars it's been won with
+fn spawn_level_label(
+    button: &mut ChildBuilder,
+    level: u32,
+    status: LevelStatus,
+    stars: usize,
+    map_icons: &MapIcons,
+) {
+    button
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(NODE_SIZE),
+                width: Val::Px(NODE_SIZE),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Name::new("BoardButton Label"),
+        ))
+        .with_children(|label| {
+            let text_color = if status == LevelStatus::Locked {
+                GRAY_600
+            } else {
+                GRAY_50
+            };
+            label.spawn((
+                Text::new(level.to_string()),
+                TextLayout {
+                    justify: JustifyText::Center,
+                    ..default()
+                },
+                TextColor(text_color.into()),
+                Name::new("BoardButton Text"),
+            ));
 
-    (
-        Text::new(area.as_str()),
-        TextLayout {
-            justify: JustifyText::Center,
-            ..default()
-        },
-        TextColor(text_color.into()),
-        Node {
-            margin: UiRect::all(Val::Auto),
-            ..default()
-        },
-        Name::new("BoardButton Text"),
-    )
+            if status != LevelStatus::Completed {
+                return;
+            }
+
+            label.spawn(Node::default()).with_children(|star_row| {
+                for star in 0..3 {
+                    let icon = if star < stars {
+                        MapIcon::Star
+                    } else {
+                        MapIcon::EmptyStar
+                    };
+                    star_row.spawn((
+                        map_icons.image(icon),
+                        Node {
+                            width: Val::Px(NODE_SIZE / 3.),
+                            height: Val::Px(NODE_SIZE / 3.),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+        });
 }
 
+// Locked levels shake instead of starting, while won ones can be replayed for a better score
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn go_to_board_on_click(
     mut state: ResMut<NextState<GameState>>,
     mut current_map: ResMut<CurrentMap>,
-    map_finishes: ResMut<MapFinishes>,
+    map_finishes: Res<MapFinishes>,
     mut needed_matches: ResMut<NeededMatches>,
-    mut interaction_query: Query<(&Interaction, &BoardButton), Changed<Interaction>>,
+    mut level_obstacles: ResMut<LevelObstacles>,
+    mut objective: ResMut<Objective>,
+    mut color_count: ResMut<ColorCount>,
+    mut move_limit: ResMut<MoveLimit>,
+    campaign_map: Res<CampaignMapHandle>,
+    campaign_maps: Res<Assets<CampaignMap>>,
+    mut interaction_query: Query<
+        (
+            Entity,
+            &Interaction,
+            &BoardButton,
+            &Node,
+            Option<&mut Shake>,
+        ),
+        Changed<Interaction>,
+    >,
+    mut commands: Commands,
 ) {
-    let mut configure_board = |next_needed_matches: u32, next_map: CurrentMap| {
-        needed_matches.0 = next_needed_matches;
-        *current_map = next_map;
-        state.set(GameState::Board);
+    let Some(campaign_map) = campaign_maps.get(&campaign_map.0) else {
+        return;
     };
 
-    for (interaction, board_button) in &mut interaction_query {
+    for (entity, interaction, board_button, node, shake) in &mut interaction_query {
         if *interaction != Interaction::Pressed {
             continue;
         }
+        let Some((index, map_node)) = campaign_map.node(board_button.0) else {
+            continue;
+        };
 
-        if !board_button.map_available(&map_finishes) {
-            return;
+        if campaign_map.status(index, &map_finishes) == LevelStatus::Locked {
+            match (shake, node.left) {
+                (Some(mut shake), _) => shake.timer.reset(),
+                (None, Val::Px(left)) => {
+                    commands.entity(entity).insert(Shake {
+                        timer: Timer::from_seconds(SHAKE_TIME, TimerMode::Once),
+                        left,
+                    });
+                }
+                _ => {}
+            }
+            continue;
         }
 
-        match *board_button {
-            BoardButton::First => configure_board(10, CurrentMap::One),
-            BoardButton::Second => configure_board(20, CurrentMap::Two),
-            BoardButton::Third => configure_board(30, CurrentMap::Three),
-            BoardButton::Fourth => configure_board(40, CurrentMap::Four),
+        needed_matches.0 = map_node.needed_matches;
+        *current_map = CurrentMap(Some(map_node.level));
+        *level_obstacles = map_node.obstacles;
+        *objective = map_node.objective;
+        color_count.0 = map_node.color_count;
+        move_limit.0 = map_node.move_limit;
+        state.set(GameState::Briefing);
+    }
+}
+
+fn shake_board_buttons(
+    time: Res<Time>,
+    mut shaking: Query<(Entity, &mut Node, &mut Shake)>,
+    mut commands: Commands,
+) {
+    for (entity, mut node, mut shake) in shaking.iter_mut() {
+        shake.timer.tick(time.delta());
+
+        if shake.timer.finished() {
+            node.left = Val::Px(shake.left);
+            commands.entity(entity).remove::<Shake>();
+            continue;
         }
+
+        let elapsed = shake.timer.elapsed_secs();
+        let offset = (elapsed * 60.).sin() * SHAKE_DISTANCE * shake.timer.fraction_remaining();
+        node.left = Val::Px(shake.left + offset);
+    }
+}
+
+// Nodes grow under the pointer and shrink while pressed, whether they can be played or not
+#[allow(clippy::type_complexity)]
+fn highlight_board_buttons(
+    mut board_buttons: Query<
+        (&Interaction, &mut Transform),
+        (Changed<Interaction>, With<BoardButton>),
+    >,
+) {
+    for (interaction, mut transform) in board_buttons.iter_mut() {
+        transform.scale = Vec3::splat(match interaction {
+            Interaction::Pressed => 0.9,
+            Interaction::Hovered => 1.15,
+            Interaction::None => 1.0,
+        });
+    }
+}
+
+// Describes the hovered level, or the focused one when nothing is hovered, next to its node
+#[allow(clippy::type_complexity)]
+fn show_level_tooltip(
+    board_buttons: Query<(&BoardButton, &Interaction, &Node, Has<Focused>)>,
+    mut tooltip: Query<(&mut Node, &mut Visibility), (With<LevelTooltip>, Without<BoardButton>)>,
+    mut tooltip_text: Query<&mut Text, With<LevelTooltipText>>,
+    map_finishes: Res<MapFinishes>,
+    best_scores: Res<BestScores>,
+    campaign_map: Res<CampaignMapHandle>,
+    campaign_maps: Res<Assets<CampaignMap>>,
+) {
+    let (Ok((mut tooltip_node, mut visibility)), Ok(mut text)) =
+        (tooltip.get_single_mut(), tooltip_text.get_single_mut())
+    else {
+        return;
+    };
+
+    let hovered = board_buttons
+        .iter()
+        .find(|(_, interaction, _, _)| **interaction != Interaction::None)
+        .or_else(|| board_buttons.iter().find(|(_, _, _, focused)| *focused));
+
+    let Some((board_button, _, node, _)) = hovered else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let (Val::Px(left), Val::Px(top)) = (node.left, node.top) else {
+        return;
+    };
+
+    let Some(campaign_map) = campaign_maps.get(&campaign_map.0) else {
+        return;
+    };
+    let Some((index, map_node)) = campaign_map.node(board_button.0) else {
+        return;
+    };
+    let map_width = campaign_map.size.0;
+
+    // On whichever side of the node has room
+    tooltip_node.left = if left + NODE_SIZE + TOOLTIP_WIDTH < map_width {
+        Val::Px(left + NODE_SIZE + TOOLTIP_GAP)
+    } else {
+        Val::Px(left - TOOLTIP_WIDTH - TOOLTIP_GAP)
+    };
+    tooltip_node.top = Val::Px(top);
+    *visibility = Visibility::Inherited;
+
+    let level = map_node.level;
+    let status = match campaign_map.status(index, &map_finishes) {
+        LevelStatus::Locked => match index.checked_sub(1) {
+            Some(previous) => format!(
+                "Locked, win level {} to unlock",
+                campaign_map.levels[previous].level
+            ),
+            None => "Locked".to_string(),
+        },
+        LevelStatus::Available => "Click to see the briefing".to_string(),
+        LevelStatus::Completed => format!(
+            "Won, best score {}\nClick to play again",
+            best_scores.get(level).unwrap_or_default()
+        ),
+    };
+
+    let mut lines = vec![
+        format!("Level {level}"),
+        map_node.objective.text(map_node.needed_matches),
+    ];
+    lines.extend(map_node.obstacles.text());
+    lines.extend(
+        map_node
+            .move_limit
+            .map(|move_limit| format!("{move_limit} moves")),
+    );
+    lines.push(status);
+    text.0 = lines.join("\n");
+}
+
+struct CampaignMapLoader;
+
+impl AssetLoader for CampaignMapLoader {
+    type Asset = CampaignMap;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<CampaignMap, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes::<CampaignMap>(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node() -> MapNode {
+        MapNode {
+            level: 1,
+            position: (0., 0.),
+            star_scores: [100, 200, 300],
+            objective: Objective::Matches,
+            needed_matches: 10,
+            obstacles: LevelObstacles::default(),
+            color_count: 4,
+            move_limit: None,
+            unlocks: None,
+        }
+    }
+
+    #[test]
+    fn campaign_map_asset_parses() {
+        let campaign_map =
+            ron::de::from_str::<CampaignMap>(include_str!("../assets/map/campaign.map.ron"))
+                .unwrap();
+
+        assert_eq!(campaign_map.last_level(), Some(5));
+        assert_eq!(campaign_map.levels[2].objective, Objective::ClearIce);
+        assert_eq!(campaign_map.levels[0].move_limit, None);
+        assert_eq!(campaign_map.levels[4].move_limit, Some(45));
+    }
+
+    #[test]
+    fn levels_open_up_in_campaign_order() {
+        let campaign_map =
+            ron::de::from_str::<CampaignMap>(include_str!("../assets/map/campaign.map.ron"))
+                .unwrap();
+        let mut map_finishes = MapFinishes::default();
+        let statuses = |map_finishes: &MapFinishes| {
+            (0..3)
+                .map(|i| campaign_map.status(i, map_finishes))
+                .collect::<Vec<_>>()
+        };
+
+        use LevelStatus::*;
+        assert_eq!(statuses(&map_finishes), [Available, Locked, Locked]);
+        map_finishes.finish(1);
+        assert_eq!(statuses(&map_finishes), [Completed, Available, Locked]);
+    }
+
+    #[test]
+    fn specials_unlock_as_their_levels_are_won() {
+        let campaign_map =
+            ron::de::from_str::<CampaignMap>(include_str!("../assets/map/campaign.map.ron"))
+                .unwrap();
+        let mut map_finishes = MapFinishes::default();
+
+        assert_eq!(campaign_map.unlocked_specials(&map_finishes), []);
+        map_finishes.finish(1);
+        map_finishes.finish(3);
+        assert_eq!(
+            campaign_map.unlocked_specials(&map_finishes),
+            [Shape::Bomb, Shape::Eliminator]
+        );
+    }
+
+    #[test]
+    fn winning_is_worth_a_star_whatever_the_score() {
+        assert_eq!(node().stars(None), 1);
+        assert_eq!(node().stars(Some(0)), 1);
+        assert_eq!(node().stars(Some(100)), 1);
+    }
+
+    #[test]
+    fn each_star_score_reached_is_a_star() {
+        assert_eq!(node().stars(Some(199)), 1);
+        assert_eq!(node().stars(Some(200)), 2);
+        assert_eq!(node().stars(Some(300)), 3);
+        assert_eq!(node().stars(Some(10_000)), 3);
     }
 }
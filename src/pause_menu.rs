@@ -0,0 +1,172 @@
+use bevy::{input::InputSystem, prelude::*, ui::FocusPolicy};
+
+use crate::{
+    board::{restart_level, Selected},
+    focus::{cancel_just_pressed, Focusable, Focused},
+    GameState, LevelState,
+};
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Replay,
+    Settings,
+    Quit,
+}
+
+impl PauseButton {
+    // Top to bottom
+    const ALL: [PauseButton; 5] = [
+        PauseButton::Resume,
+        PauseButton::Restart,
+        PauseButton::Replay,
+        PauseButton::Settings,
+        PauseButton::Quit,
+    ];
+
+    fn text(&self) -> &'static str {
+        match self {
+            PauseButton::Resume => "Resume",
+            PauseButton::Restart => "Restart level",
+            PauseButton::Replay => "Replay same board",
+            PauseButton::Settings => "Settings",
+            PauseButton::Quit => "Quit to map",
+        }
+    }
+}
+
+pub fn pause_menu(app: &mut App) {
+    app.add_systems(OnEnter(LevelState::Paused), setup)
+        .add_systems(OnExit(LevelState::Paused), cleanup)
+        // Before the board's own input in Update, so that Esc either pauses or cancels the
+        // selection, not both
+        .add_systems(
+            PreUpdate,
+            (
+                pause.run_if(in_state(LevelState::Playing)),
+                resume.run_if(in_state(LevelState::Paused)),
+            )
+                .after(InputSystem),
+        )
+        .add_systems(
+            FixedUpdate,
+            press_pause_buttons.run_if(in_state(LevelState::Paused)),
+        );
+}
+
+fn pause_just_pressed(keys: &ButtonInput<KeyCode>, gamepads: &Query<&Gamepad>) -> bool {
+    keys.just_pressed(KeyCode::KeyP)
+        || gamepads
+            .iter()
+            .any(|g| g.just_pressed(GamepadButton::Start))
+}
+
+fn pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    selected: Query<(), With<Selected>>,
+    mut level_state: ResMut<NextState<LevelState>>,
+) {
+    let escape = keys.just_pressed(KeyCode::Escape) && selected.is_empty();
+    if escape || pause_just_pressed(&keys, &gamepads) {
+        level_state.set(LevelState::Paused);
+    }
+}
+
+fn resume(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut level_state: ResMut<NextState<LevelState>>,
+) {
+    if cancel_just_pressed(&keys, &gamepads) || pause_just_pressed(&keys, &gamepads) {
+        level_state.set(LevelState::Playing);
+    }
+}
+
+fn setup(mut commands: Commands, focused: Query<Entity, With<Focused>>) {
+    // The board cursor gets its focus back when play resumes
+    for focused in focused.iter() {
+        commands.entity(focused).remove::<Focused>();
+    }
+
+    commands
+        .spawn((
+            PauseMenu,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Vw(100.),
+                height: Val::Vh(100.),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(10.),
+                ..default()
+            },
+            BackgroundColor(Srgba::new(0.0, 0.0, 0.0, 0.8).into()),
+            // Keeps clicks from reaching the board underneath
+            FocusPolicy::Block,
+            GlobalZIndex(2),
+            Name::new("PauseMenu Root"),
+        ))
+        .with_children(|root| {
+            // The list is reversed so that up moves focus up, like on the map
+            for (i, pause_button) in PauseButton::ALL.iter().rev().enumerate() {
+                let mut button = root.spawn((
+                    *pause_button,
+                    Button,
+                    Node {
+                        width: Val::Px(300.),
+                        padding: UiRect::all(Val::Px(10.)),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(Srgba::new(1.0, 1.0, 1.0, 0.1).into()),
+                    Focusable(i as u32),
+                ));
+                button.with_child(Text::new(pause_button.text()));
+
+                if *pause_button == PauseButton::Resume {
+                    button.insert(Focused);
+                }
+            }
+
+            root.spawn((
+                Text::new("Paused"),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn press_pause_buttons(
+    interactions: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+    mut level_state: ResMut<NextState<LevelState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    for (interaction, pause_button) in interactions.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match pause_button {
+            PauseButton::Resume => level_state.set(LevelState::Playing),
+            PauseButton::Restart | PauseButton::Replay => {
+                commands.queue(restart_level(*pause_button == PauseButton::Replay));
+                level_state.set(LevelState::Playing);
+            }
+            PauseButton::Settings => game_state.set(GameState::Settings),
+            PauseButton::Quit => game_state.set(GameState::Map),
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, pause_menu: Query<Entity, With<PauseMenu>>) {
+    commands.entity(pause_menu.single()).despawn_recursive();
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::focus::{Focusable, Focused};
+
+// The level complete and win screens both show a title, a list of stats and a row of buttons
+pub trait ResultsButton: Component + Copy + PartialEq {
+    // Left to right
+    const ALL: &'static [Self];
+    const FOCUSED: Self;
+
+    fn text(&self) -> &'static str;
+}
+
+pub fn spawn_results_screen<B: ResultsButton>(
+    commands: &mut Commands,
+    screen: impl Component,
+    name: &str,
+    title: &str,
+    stats: &[String],
+) {
+    commands
+        .spawn((
+            screen,
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.),
+                margin: UiRect::all(Val::Auto),
+                ..default()
+            },
+            Name::new(format!("{name} Root")),
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new(title),
+                TextFont {
+                    font_size: 60.0,
+                    ..default()
+                },
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+            ));
+
+            for stat in stats {
+                root.spawn(Text::new(stat));
+            }
+
+            root.spawn((
+                Node {
+                    column_gap: Val::Px(20.),
+                    margin: UiRect::top(Val::Px(30.)),
+                    ..default()
+                },
+                Name::new(format!("{name} Buttons")),
+            ))
+            .with_children(|buttons| {
+                for (i, results_button) in B::ALL.iter().enumerate() {
+                    let mut button = buttons.spawn((
+                        *results_button,
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(20.), Val::Px(10.)),
+                            ..default()
+                        },
+                        BackgroundColor(Srgba::new(1.0, 1.0, 1.0, 0.1).into()),
+                        Focusable(i as u32),
+                    ));
+                    button.with_child(Text::new(results_button.text()));
+
+                    if *results_button == B::FOCUSED {
+                        button.insert(Focused);
+                    }
+                }
+            });
+        });
+}
+
+// As minutes and seconds, e.g 2:05
+pub fn format_time(seconds: f32) -> String {
+    let minutes = (seconds / 60.0) as u32;
+    let seconds = (seconds % 60.0) as u32;
+    format!("{minutes}:{seconds:02}")
+}
@@ -0,0 +1,416 @@
+use bevy::{
+    prelude::*,
+    ui::FocusPolicy,
+    window::{MonitorSelection, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::shape::Palette,
+    focus::{cancel_just_pressed, Focusable, Focused},
+    GameState,
+};
+
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub palette: Palette,
+    // From 0.0 to 1.0
+    pub music_volume: f32,
+    pub effects_volume: f32,
+    // Scales how fast shapes swap, clear and fall
+    pub animation_speed: f32,
+    pub input_mode: InputMode,
+    // Seconds without a move before a possible one is pointed out, or no hints
+    pub hint_delay: Option<f32>,
+    pub fullscreen: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            palette: Palette::default(),
+            music_volume: 0.5,
+            effects_volume: 0.8,
+            animation_speed: 1.0,
+            input_mode: InputMode::default(),
+            hint_delay: Some(5.0),
+            fullscreen: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+pub enum InputMode {
+    #[default]
+    ClickAndDrag,
+    Click,
+    Drag,
+}
+
+impl InputMode {
+    pub fn allows_click(&self) -> bool {
+        *self != InputMode::Drag
+    }
+
+    pub fn allows_drag(&self) -> bool {
+        *self != InputMode::Click
+    }
+}
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+impl Settings {
+    // Missing or unreadable settings fall back to the defaults
+    fn load() -> Self {
+        read_saved_settings()
+            .and_then(|saved| ron::from_str(&saved).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        match ron::ser::to_string_pretty(self, default()) {
+            Ok(serialized) => write_saved_settings(&serialized),
+            Err(error) => warn!("Couldn't serialize settings: {error}"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_saved_settings() -> Option<String> {
+    std::fs::read_to_string(SETTINGS_FILE).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_saved_settings(serialized: &str) {
+    if let Err(error) = std::fs::write(SETTINGS_FILE, serialized) {
+        warn!("Couldn't save settings: {error}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_saved_settings() -> Option<String> {
+    local_storage()?.get_item(SETTINGS_FILE).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_saved_settings(serialized: &str) {
+    if let Some(storage) = local_storage() {
+        if storage.set_item(SETTINGS_FILE, serialized).is_err() {
+            warn!("Couldn't save settings");
+        }
+    }
+}
+
+#[derive(Component)]
+struct SettingsScreen;
+
+// Opens the settings from whichever screen it's on
+#[derive(Component)]
+pub struct OpenSettings;
+
+// Where to go back to when the settings are closed
+#[derive(Resource)]
+struct ReturnState(GameState);
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum SettingsButton {
+    MusicVolume,
+    EffectsVolume,
+    AnimationSpeed,
+    Palette,
+    InputMode,
+    HintDelay,
+    WindowMode,
+    Back,
+}
+
+impl SettingsButton {
+    // Top to bottom
+    const ALL: [SettingsButton; 8] = [
+        SettingsButton::MusicVolume,
+        SettingsButton::EffectsVolume,
+        SettingsButton::AnimationSpeed,
+        SettingsButton::Palette,
+        SettingsButton::InputMode,
+        SettingsButton::HintDelay,
+        SettingsButton::WindowMode,
+        SettingsButton::Back,
+    ];
+
+    fn text(&self, settings: &Settings) -> String {
+        let percent = |volume: f32| format!("{}%", (volume * 100.0).round());
+
+        match self {
+            SettingsButton::MusicVolume => {
+                format!("Music volume: {}", percent(settings.music_volume))
+            }
+            SettingsButton::EffectsVolume => {
+                format!("Effects volume: {}", percent(settings.effects_volume))
+            }
+            SettingsButton::AnimationSpeed => {
+                format!("Animation speed: {}x", settings.animation_speed)
+            }
+            SettingsButton::Palette => format!("Palette: {}", settings.palette.name()),
+            SettingsButton::InputMode => format!(
+                "Input: {}",
+                match settings.input_mode {
+                    InputMode::ClickAndDrag => "Click and drag",
+                    InputMode::Click => "Click",
+                    InputMode::Drag => "Drag",
+                }
+            ),
+            SettingsButton::HintDelay => match settings.hint_delay {
+                Some(delay) => format!("Hints: after {delay}s"),
+                None => "Hints: Off".to_string(),
+            },
+            SettingsButton::WindowMode => match settings.fullscreen {
+                true => "Window: Fullscreen".to_string(),
+                false => "Window: Windowed".to_string(),
+            },
+            SettingsButton::Back => "Back".to_string(),
+        }
+    }
+
+    // Pressing a setting moves it on to its next option
+    fn press(&self, settings: &mut Settings) {
+        match self {
+            SettingsButton::MusicVolume => {
+                settings.music_volume = next(&[0.0, 0.25, 0.5, 0.75, 1.0], settings.music_volume)
+            }
+            SettingsButton::EffectsVolume => {
+                settings.effects_volume =
+                    next(&[0.0, 0.25, 0.5, 0.75, 1.0], settings.effects_volume)
+            }
+            SettingsButton::AnimationSpeed => {
+                settings.animation_speed = next(&[0.5, 1.0, 1.5, 2.0], settings.animation_speed)
+            }
+            SettingsButton::Palette => settings.palette = settings.palette.next(),
+            SettingsButton::InputMode => {
+                settings.input_mode = next(
+                    &[InputMode::ClickAndDrag, InputMode::Click, InputMode::Drag],
+                    settings.input_mode,
+                )
+            }
+            SettingsButton::HintDelay => {
+                settings.hint_delay = next(
+                    &[None, Some(3.0), Some(5.0), Some(10.0)],
+                    settings.hint_delay,
+                )
+            }
+            SettingsButton::WindowMode => settings.fullscreen = !settings.fullscreen,
+            SettingsButton::Back => {}
+        }
+
+        // The option after the current one, or the first if the current one isn't an option
+        fn next<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+            let index = options.iter().position(|o| *o == current);
+            options[index.map_or(0, |i| (i + 1) % options.len())]
+        }
+    }
+}
+
+pub fn settings(app: &mut App) {
+    app.insert_resource(Settings::load())
+        .insert_resource(ReturnState(GameState::StartScreen))
+        .add_systems(
+            OnEnter(GameState::Settings),
+            (remember_return_state, setup).chain(),
+        )
+        .add_systems(OnExit(GameState::Settings), cleanup)
+        .add_systems(
+            FixedUpdate,
+            (
+                open_settings_on_click,
+                press_settings_buttons.run_if(in_state(GameState::Settings)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                close_settings_on_cancel.run_if(in_state(GameState::Settings)),
+                update_settings_text.run_if(in_state(GameState::Settings)),
+                apply_window_mode.run_if(resource_changed::<Settings>),
+                save_settings
+                    .run_if(resource_changed::<Settings>)
+                    .run_if(not(resource_added::<Settings>)),
+            ),
+        );
+}
+
+// Settings can be opened during play, so the board is kept rather than torn down and rebuilt
+pub fn opening_settings(mut transitions: EventReader<StateTransitionEvent<GameState>>) -> bool {
+    transitions
+        .read()
+        .last()
+        .is_some_and(|t| t.entered == Some(GameState::Settings))
+}
+
+pub fn closing_settings(mut transitions: EventReader<StateTransitionEvent<GameState>>) -> bool {
+    transitions
+        .read()
+        .last()
+        .is_some_and(|t| t.exited == Some(GameState::Settings))
+}
+
+pub fn spawn_settings_button<'a>(parent: &'a mut ChildBuilder) -> EntityCommands<'a> {
+    let mut button = parent.spawn((
+        OpenSettings,
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(20.), Val::Px(10.)),
+            ..default()
+        },
+        BackgroundColor(Srgba::new(1.0, 1.0, 1.0, 0.1).into()),
+        Name::new("Settings Button"),
+    ));
+    button.with_child(Text::new("Settings"));
+    button
+}
+
+fn remember_return_state(
+    mut transitions: EventReader<StateTransitionEvent<GameState>>,
+    mut return_state: ResMut<ReturnState>,
+) {
+    if let Some(exited) = transitions.read().last().and_then(|t| t.exited) {
+        return_state.0 = exited;
+    }
+}
+
+fn setup(mut commands: Commands, settings: Res<Settings>, focused: Query<Entity, With<Focused>>) {
+    // A board left open underneath keeps its focus otherwise
+    for focused in focused.iter() {
+        commands.entity(focused).remove::<Focused>();
+    }
+
+    commands
+        .spawn((
+            SettingsScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Vw(100.),
+                height: Val::Vh(100.),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(10.),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+            // Keeps clicks from reaching the board underneath
+            FocusPolicy::Block,
+            GlobalZIndex(2),
+            Name::new("SettingsScreen Root"),
+        ))
+        .with_children(|root| {
+            // The list is reversed so that up moves focus up, like on the map
+            for (i, settings_button) in SettingsButton::ALL.iter().rev().enumerate() {
+                let mut button = root.spawn((
+                    *settings_button,
+                    Button,
+                    Node {
+                        width: Val::Px(400.),
+                        padding: UiRect::all(Val::Px(10.)),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(Srgba::new(1.0, 1.0, 1.0, 0.1).into()),
+                    Focusable(i as u32),
+                ));
+                button.with_child(Text::new(settings_button.text(&settings)));
+
+                if *settings_button == SettingsButton::ALL[0] {
+                    button.insert(Focused);
+                }
+            }
+
+            root.spawn((
+                Text::new("Settings"),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn open_settings_on_click(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<OpenSettings>)>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if interactions.iter().any(|i| *i == Interaction::Pressed) {
+        game_state.set(GameState::Settings);
+    }
+}
+
+fn press_settings_buttons(
+    interactions: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    return_state: Res<ReturnState>,
+    mut settings: ResMut<Settings>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, settings_button) in interactions.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if *settings_button == SettingsButton::Back {
+            game_state.set(return_state.0);
+        } else {
+            settings_button.press(&mut settings);
+        }
+    }
+}
+
+fn close_settings_on_cancel(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    return_state: Res<ReturnState>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if cancel_just_pressed(&keys, &gamepads) {
+        game_state.set(return_state.0);
+    }
+}
+
+fn update_settings_text(
+    settings: Res<Settings>,
+    buttons: Query<(&SettingsButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (settings_button, children) in buttons.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.0 = settings_button.text(&settings);
+            }
+        }
+    }
+}
+
+fn apply_window_mode(settings: Res<Settings>, mut window: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = window.get_single_mut() {
+        window.mode = match settings.fullscreen {
+            true => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            false => WindowMode::Windowed,
+        };
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+fn cleanup(mut commands: Commands, settings_screen: Query<Entity, With<SettingsScreen>>) {
+    commands
+        .entity(settings_screen.single())
+        .despawn_recursive();
+}
@@ -1,6 +1,10 @@
 use bevy::prelude::*;
 
-use crate::GameState;
+use crate::{
+    focus::{Focusable, Focused},
+    settings::spawn_settings_button,
+    GameState,
+};
 
 #[derive(Component)]
 struct StartScreen;
@@ -29,16 +33,19 @@ fn setup(mut commands: Commands) {
             StartScreen,
             Name::new("StartScreen Root"),
             Button,
+            Focusable(0),
+            Focused,
         ))
         .with_children(|root| {
             root.spawn(Text::new("Joel's Match 3 Game With Rust And Bevy"));
-            root.spawn(Text::new("Click to start"));
+            root.spawn(Text::new("Click or press Enter to start"));
+            spawn_settings_button(root).insert(Focusable(1));
         });
 }
 
 fn start_if_clicked(
     mut game_state: ResMut<NextState<GameState>>,
-    interaction: Query<&Interaction, Changed<Interaction>>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<StartScreen>)>,
 ) {
     if interaction.iter().any(|i| *i == Interaction::Pressed) {
         game_state.set(GameState::Map);
@@ -0,0 +1,312 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    board::{shape::Shape, AllowedSwaps, BOARD_SIZE},
+    CurrentMap,
+};
+
+// Pages explaining whatever was just unlocked, read from `assets/tutorials`
+#[derive(Asset, TypePath)]
+pub struct Tutorials(Vec<TutorialPage>);
+
+pub struct TutorialPage {
+    pub unlocked_by: Unlock,
+    pub caption: String,
+    // Still pictures, shown when there's no demo
+    pub boards: Vec<TutorialBoard>,
+    pub demo: Option<TutorialDemo>,
+}
+
+// Row by row from the top-left, like the board's children. `None` is an empty cell.
+pub type TutorialBoard = [Option<Shape>; BOARD_SIZE * BOARD_SIZE];
+
+// Moves played out on a live board, then repeated by the player
+pub struct TutorialDemo {
+    pub board: [Shape; BOARD_SIZE * BOARD_SIZE],
+    pub steps: Vec<DemoStep>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum DemoStep {
+    // The shapes at these 1-based (row, col) positions
+    Swap((i32, i32), (i32, i32)),
+    // Whichever special is on the board with one of its neighbours, wherever it ended up
+    Detonate,
+}
+
+impl DemoStep {
+    pub fn allowed_swaps(&self) -> AllowedSwaps {
+        match *self {
+            DemoStep::Swap(a, b) => AllowedSwaps::Between(a, b),
+            DemoStep::Detonate => AllowedSwaps::WithSpecial,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Unlock {
+    // Winning this level for the first time
+    Level(u32),
+}
+
+impl Unlock {
+    pub fn from_current_map(current_map: &CurrentMap) -> Option<Unlock> {
+        current_map.level().map(Unlock::Level)
+    }
+}
+
+// A level's board and first moves picked for the player the first time they play it, with
+// each move pointed out
+#[derive(Asset, TypePath)]
+pub struct Guide {
+    pub level: u32,
+    pub board: [Shape; BOARD_SIZE * BOARD_SIZE],
+    pub steps: Vec<GuideStep>,
+    // Shown once the player is left to carry on alone
+    pub outro: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GuideStep {
+    // 1-based (row, col) positions, like `DemoStep::Swap`
+    pub swap: ((i32, i32), (i32, i32)),
+    pub text: String,
+}
+
+impl Tutorials {
+    pub fn page(&self, unlock: Unlock) -> Option<&TutorialPage> {
+        self.0.iter().find(|page| page.unlocked_by == unlock)
+    }
+}
+
+#[derive(Resource)]
+pub struct TutorialsHandle(pub Handle<Tutorials>);
+
+#[derive(Resource)]
+pub struct GuideHandle(pub Handle<Guide>);
+
+pub fn tutorial(app: &mut App) {
+    app.init_asset::<Tutorials>()
+        .init_asset::<Guide>()
+        .register_asset_loader(TutorialsLoader)
+        .register_asset_loader(GuideLoader)
+        .add_systems(Startup, load_tutorials);
+}
+
+fn load_tutorials(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(TutorialsHandle(
+        asset_server.load("tutorials/unlocks.tutorials.ron"),
+    ));
+    commands.insert_resource(GuideHandle(
+        asset_server.load("tutorials/first-level.guide.ron"),
+    ));
+}
+
+// The board can't be set up before this, or the guided level would start without its guide
+pub fn guide_loaded(asset_server: Res<AssetServer>, guide_handle: Res<GuideHandle>) -> bool {
+    asset_server.is_loaded(&guide_handle.0)
+}
+
+// How pages are written in the asset files, with boards as rows of characters
+#[derive(Deserialize)]
+struct TutorialPageFile {
+    unlocked_by: Unlock,
+    caption: String,
+    #[serde(default)]
+    boards: Vec<Vec<String>>,
+    #[serde(default)]
+    demo: Option<TutorialDemoFile>,
+}
+
+#[derive(Deserialize)]
+struct TutorialDemoFile {
+    board: Vec<String>,
+    steps: Vec<DemoStep>,
+}
+
+struct TutorialsLoader;
+
+impl AssetLoader for TutorialsLoader {
+    type Asset = Tutorials;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Tutorials, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let pages = ron::de::from_bytes::<Vec<TutorialPageFile>>(&bytes)?;
+
+        let pages = pages
+            .into_iter()
+            .map(|page| {
+                Ok(TutorialPage {
+                    unlocked_by: page.unlocked_by,
+                    caption: page.caption,
+                    boards: page
+                        .boards
+                        .iter()
+                        .map(|rows| parse_board(rows))
+                        .collect::<Result<_, _>>()?,
+                    demo: page.demo.map(parse_demo).transpose()?,
+                })
+            })
+            .collect::<Result<_, Self::Error>>()?;
+
+        Ok(Tutorials(pages))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tutorials.ron"]
+    }
+}
+
+fn parse_board(rows: &[String]) -> Result<TutorialBoard, String> {
+    let cells = rows
+        .iter()
+        .flat_map(|row| row.chars())
+        .map(|c| match c {
+            '.' => Ok(None),
+            c => shape_from_char(c)
+                .map(Some)
+                .ok_or_else(|| format!("Unknown shape '{c}' in tutorial board")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let cell_count = cells.len();
+    cells.try_into().map_err(|_| {
+        format!(
+            "Tutorial boards need {} cells, found {cell_count}",
+            BOARD_SIZE * BOARD_SIZE
+        )
+    })
+}
+
+fn parse_demo(demo: TutorialDemoFile) -> Result<TutorialDemo, String> {
+    Ok(TutorialDemo {
+        board: parse_live_board(&demo.board)?,
+        steps: demo.steps,
+    })
+}
+
+// Boards that are played on can't have gaps
+fn parse_live_board(rows: &[String]) -> Result<[Shape; BOARD_SIZE * BOARD_SIZE], String> {
+    let board = parse_board(rows)?;
+    if board.iter().any(Option::is_none) {
+        return Err("Boards that are played on can't have empty cells".to_string());
+    }
+
+    Ok(board.map(Option::unwrap))
+}
+
+#[derive(Deserialize)]
+struct GuideFile {
+    level: u32,
+    board: Vec<String>,
+    steps: Vec<GuideStep>,
+    outro: String,
+}
+
+struct GuideLoader;
+
+impl AssetLoader for GuideLoader {
+    type Asset = Guide;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Guide, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let guide = ron::de::from_bytes::<GuideFile>(&bytes)?;
+
+        Ok(Guide {
+            level: guide.level,
+            board: parse_live_board(&guide.board)?,
+            steps: guide.steps,
+            outro: guide.outro,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["guide.ron"]
+    }
+}
+
+fn shape_from_char(c: char) -> Option<Shape> {
+    Some(match c {
+        'R' => Shape::Red,
+        'B' => Shape::Blue,
+        'G' => Shape::Green,
+        'P' => Shape::Pink,
+        'O' => Shape::Orange,
+        'V' => Shape::Purple,
+        'Y' => Shape::Yellow,
+        '*' => Shape::Bomb,
+        '-' => Shape::HorizontalLiner,
+        '|' => Shape::VerticalLiner,
+        'E' => Shape::Eliminator,
+        '+' => Shape::Cross,
+        'I' => Shape::Ice,
+        'S' => Shape::Stone,
+        'C' => Shape::Chocolate,
+        'N' => Shape::Ingredient,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_shapes_and_gaps_row_by_row() {
+        let board = parse_board(&rows(&["RB...", ".....", "..*..", ".....", "...IN"])).unwrap();
+
+        assert_eq!(board[0], Some(Shape::Red));
+        assert_eq!(board[1], Some(Shape::Blue));
+        assert_eq!(board[2], None);
+        assert_eq!(board[2 * BOARD_SIZE + 2], Some(Shape::Bomb));
+        assert_eq!(board[4 * BOARD_SIZE + 3], Some(Shape::Ice));
+        assert_eq!(board[4 * BOARD_SIZE + 4], Some(Shape::Ingredient));
+    }
+
+    #[test]
+    fn rejects_unknown_shapes() {
+        let error = parse_board(&rows(&["RBGPX", "RBGPO", "RBGPO", "RBGPO", "RBGPO"])).unwrap_err();
+
+        assert!(error.contains("'X'"));
+    }
+
+    #[test]
+    fn rejects_boards_of_the_wrong_size() {
+        assert!(parse_board(&rows(&["RBGPO", "RBGPO"])).is_err());
+        assert!(parse_board(&rows(&["RBGPOR", "RBGPO", "RBGPO", "RBGPO", "RBGPO"])).is_err());
+    }
+
+    #[test]
+    fn live_boards_are_full() {
+        let full = rows(&["RBGPO", "BGPOR", "GPORB", "PORBG", "ORBGP"]);
+        let board = parse_live_board(&full).unwrap();
+        assert_eq!(board[BOARD_SIZE], Shape::Blue);
+
+        let with_gap = rows(&["RBGPO", "BGPOR", "GP.RB", "PORBG", "ORBGP"]);
+        assert!(parse_live_board(&with_gap).is_err());
+    }
+}
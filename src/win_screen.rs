@@ -1,24 +1,89 @@
 use bevy::prelude::*;
 
-use crate::GameState;
+use crate::{
+    results_screen::{format_time, spawn_results_screen, ResultsButton},
+    BestScores, CampaignStats, GameState, MapFinishes,
+};
 
 #[derive(Component)]
 pub struct WinScreen;
 
+#[derive(Component, Clone, Copy, PartialEq)]
+enum WinScreenButton {
+    Map,
+    Replay,
+    NewCampaign,
+}
+
+impl ResultsButton for WinScreenButton {
+    const ALL: &'static [WinScreenButton] = &[
+        WinScreenButton::Map,
+        WinScreenButton::Replay,
+        WinScreenButton::NewCampaign,
+    ];
+    const FOCUSED: WinScreenButton = WinScreenButton::Map;
+
+    fn text(&self) -> &'static str {
+        match self {
+            WinScreenButton::Map => "Back to map",
+            WinScreenButton::Replay => "Replay level",
+            WinScreenButton::NewCampaign => "New campaign",
+        }
+    }
+}
+
 pub fn win_screen(app: &mut App) {
     app.add_systems(OnEnter(GameState::WinScreen), setup)
+        .add_systems(
+            FixedUpdate,
+            press_win_screen_buttons.run_if(in_state(GameState::WinScreen)),
+        )
         .add_systems(OnExit(GameState::WinScreen), cleanup);
 }
 
-fn setup(mut commands: Commands) {
-    commands.spawn((
+fn setup(mut commands: Commands, campaign_stats: Res<CampaignStats>) {
+    let stats = [
+        format!("Total score: {}", campaign_stats.score),
+        format!("Moves: {}", campaign_stats.moves),
+        format!("Best combo: {}", campaign_stats.best_combo),
+        format!("Specials created: {}", campaign_stats.specials_created),
+        format!("Time played: {}", format_time(campaign_stats.time_played)),
+    ];
+
+    spawn_results_screen::<WinScreenButton>(
+        &mut commands,
         WinScreen,
-        Text::new("You win!"),
-        Node {
-            margin: UiRect::all(Val::Auto),
-            ..default()
-        },
-    ));
+        "WinScreen",
+        "You win!",
+        &stats,
+    );
+}
+
+// The level settings are still those of the last level, so replaying just goes back to the board
+fn press_win_screen_buttons(
+    interactions: Query<(&Interaction, &WinScreenButton), Changed<Interaction>>,
+    mut map_finishes: ResMut<MapFinishes>,
+    mut campaign_stats: ResMut<CampaignStats>,
+    mut best_scores: ResMut<BestScores>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, win_screen_button) in interactions.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match win_screen_button {
+            WinScreenButton::Map => game_state.set(GameState::Map),
+            WinScreenButton::Replay => game_state.set(GameState::Board),
+            WinScreenButton::NewCampaign => {
+                *map_finishes = MapFinishes::default();
+                *campaign_stats = CampaignStats::default();
+                // Stars are earned again from scratch too
+                *best_scores = BestScores::default();
+                game_state.set(GameState::Map);
+            }
+        }
+    }
 }
 
 fn cleanup(mut commands: Commands, map: Query<Entity, With<WinScreen>>) {